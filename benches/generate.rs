@@ -0,0 +1,66 @@
+//! Benchmarks of generating long waves. The generators are parallel with the `parallel` feature,
+//! so the speedup is shown by comparing them with the serial iterator of the same wave:
+//!
+//! ```console
+//! cargo bench --features parallel
+//! ```
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use wav_gen::{gen_harmonics, gen_sine_wave, sine_samples, Harmonic, OnClip};
+
+/// 60 seconds of mono samples at 48000 hertz
+const NUMBER_SAMPLES: u32 = 60 * 48000;
+
+fn sine(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sine");
+    group.sample_size(10);
+
+    group.bench_function(BenchmarkId::new("serial", NUMBER_SAMPLES), |b| {
+        b.iter(|| {
+            sine_samples(440., 0., 32767, 48000)
+                .take(NUMBER_SAMPLES as usize)
+                .collect::<Vec<i16>>()
+        })
+    });
+    group.bench_function(BenchmarkId::new("generator", NUMBER_SAMPLES), |b| {
+        b.iter(|| gen_sine_wave(440., 0., NUMBER_SAMPLES, 1, 32767, 48000, &mut |_| {}).unwrap())
+    });
+
+    group.finish();
+}
+
+fn harmonics(c: &mut Criterion) {
+    let harmonics: Vec<Harmonic> = (1..=16)
+        .map(|n| Harmonic {
+            frequency: 110. * n as f32,
+            amplitude: 1. / (16. * n as f32),
+            phase: 0.,
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("harmonics");
+    group.sample_size(10);
+
+    for fast_sine in [false, true] {
+        let name = if fast_sine { "table" } else { "sine" };
+        group.bench_function(BenchmarkId::new(name, NUMBER_SAMPLES), |b| {
+            b.iter(|| {
+                gen_harmonics(
+                    &harmonics,
+                    NUMBER_SAMPLES,
+                    1,
+                    32767,
+                    48000,
+                    fast_sine,
+                    OnClip::Saturate,
+                    &mut |_| {},
+                )
+                .unwrap()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, sine, harmonics);
+criterion_main!(benches);
@@ -1,32 +1,127 @@
 /// Error handling
+use std::error::Error;
 use std::fmt;
 use std::path::PathBuf;
 
 pub enum WavGenError {
-    ReadError(PathBuf),
-    WriteError(PathBuf),
-    CreateError(PathBuf),
-    HarmonicParseError(usize),
+    // The file and the underlying error
+    ReadError(PathBuf, Box<dyn Error>),
+    WriteError(PathBuf, Box<dyn Error>),
+    CreateError(PathBuf, Box<dyn Error>),
+    HarmonicParseError(usize, &'static str, String), // Line number, field and its text
     NoHarmonics,
+    ZeroFrequency,
+    AboveNyquist(f32, u32),       // Frequency and sampling rate in hertz
+    NotPositiveFrequency(f32),    // Frequency in hertz
+    Clipping(u16),                // Volume
+    SampleOutOfRange(i32, usize), // Value and index of the sample
+    RngStateParseError(PathBuf),
+    ChannelSpecParseError(usize),
+    NoChannels,
+    ChannelLengthMismatch,
+    SequenceParseError(usize),
+    NoSegments,
+    MaxDurationExceeded(u32),
+    ReferenceChannelOutOfRange(usize, usize), // Reference channel and number of channels
+    ReferenceChannelDelayed(usize),
+    TooManyChannels(usize),
+    Silent(f32, f32), // Peak and threshold in dBFS
+}
+
+impl WavGenError {
+    /// The code the program exits with for the error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            WavGenError::Silent(..) => 3,
+            _ => 1,
+        }
+    }
 }
 
 //Required for the ? operator
-impl std::error::Error for WavGenError {}
+impl Error for WavGenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WavGenError::ReadError(_, source)
+            | WavGenError::WriteError(_, source)
+            | WavGenError::CreateError(_, source) => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 // Formatted display for the errors
 impl fmt::Display for WavGenError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            WavGenError::ReadError(p) => f.write_fmt(format_args!("could not read file {:?}", p)),
-            WavGenError::WriteError(p) => f.write_fmt(format_args!("could not write file {:?}", p)),
-            WavGenError::CreateError(p) => {
+            WavGenError::ReadError(p, _) => f.write_fmt(format_args!("could not read file {:?}", p)),
+            WavGenError::WriteError(p, _) => f.write_fmt(format_args!("could not write file {:?}", p)),
+            WavGenError::CreateError(p, _) => {
                 f.write_fmt(format_args!("unable to create file {:?}", p))
             }
-            WavGenError::HarmonicParseError(line_number) => f.write_fmt(format_args!(
-                "parse error in harmonic file at line {:?}",
+            WavGenError::HarmonicParseError(line_number, field, text) => f.write_fmt(
+                format_args!("line {}: could not parse {} {:?}", line_number, field, text),
+            ),
+            WavGenError::NoHarmonics => f.write_fmt(format_args!("no harmonics found")),
+            WavGenError::ZeroFrequency => f.write_fmt(format_args!(
+                "a harmonic with a frequency of 0Hz has no cycle (see --cycle)"
+            )),
+            WavGenError::AboveNyquist(frequency, sampling_rate) => f.write_fmt(format_args!(
+                "the frequency of {}Hz needs to be below half the sampling rate of {}Hz, otherwise it aliases",
+                frequency, sampling_rate
+            )),
+            WavGenError::NotPositiveFrequency(frequency) => f.write_fmt(format_args!(
+                "the frequency of {}Hz needs to be above 0Hz",
+                frequency
+            )),
+            WavGenError::Clipping(volume) => f.write_fmt(format_args!(
+                "the volume of {} is larger than the largest sample value of {} so the wave would clip",
+                volume,
+                i16::MAX
+            )),
+            WavGenError::SampleOutOfRange(value, index) => f.write_fmt(format_args!(
+                "sample {} has the value {} which is outside the range of 16 bit samples (see --on-clip)",
+                index, value
+            )),
+            WavGenError::RngStateParseError(p) => {
+                f.write_fmt(format_args!("invalid random number state in file {:?}", p))
+            }
+            WavGenError::ChannelSpecParseError(line_number) => f.write_fmt(format_args!(
+                "parse error in channels file at line {:?}",
                 line_number
             )),
-            WavGenError::NoHarmonics => f.write_fmt(format_args!("no harmonics found")),
+            WavGenError::NoChannels => f.write_fmt(format_args!("no channels found")),
+            WavGenError::ChannelLengthMismatch => {
+                f.write_fmt(format_args!("the channels do not have the same length"))
+            }
+            WavGenError::SequenceParseError(line_number) => f.write_fmt(format_args!(
+                "parse error in sequence file at line {:?}",
+                line_number
+            )),
+            WavGenError::NoSegments => f.write_fmt(format_args!("no segments found")),
+            WavGenError::MaxDurationExceeded(max_duration) => f.write_fmt(format_args!(
+                "the wave would be longer than the maximum duration of {} seconds (see --max-duration)",
+                max_duration
+            )),
+            WavGenError::ReferenceChannelOutOfRange(channel, number_channels) => {
+                f.write_fmt(format_args!(
+                    "the reference channel {} does not exist as there are only {} channel delays",
+                    channel, number_channels
+                ))
+            }
+            WavGenError::ReferenceChannelDelayed(channel) => f.write_fmt(format_args!(
+                "the delay of the reference channel {} must be 0",
+                channel
+            )),
+            WavGenError::TooManyChannels(number_channels) => f.write_fmt(format_args!(
+                "{} channels cannot be written as there can be at most {} channels",
+                number_channels,
+                u8::MAX
+            )),
+            WavGenError::Silent(peak, threshold) => f.write_fmt(format_args!(
+                "the peak of the wave ({:.1}dBFS) is below the silence threshold of {:.1}dBFS",
+                peak, threshold
+            )),
         }
     }
 }
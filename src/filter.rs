@@ -0,0 +1,242 @@
+//! Filters that can be applied to the generated samples
+use std::f64::consts::PI;
+
+/// Time constant of the limiting pole (50 kHz) that is used to stop the pre-emphasis curves
+/// from rising for ever. This is the same pole as used by the cutting lathes for RIAA.
+const LIMITING_POLE: f64 = 3.18e-6;
+
+/// A second order IIR filter in direct form I.
+///
+/// The coefficients are normalised so that `a0` is 1.
+#[derive(Clone, Copy, Debug)]
+pub struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+}
+
+impl Biquad {
+    /// Creates the filter from the coefficients of the analog transfer function
+    /// `H(s) = (b[0] + b[1]s + b[2]s²) / (a[0] + a[1]s + a[2]s²)` using the bilinear transform.
+    pub fn from_analog(b: [f64; 3], a: [f64; 3], sampling_rate: u32) -> Biquad {
+        let k = 2. * sampling_rate as f64;
+        let k2 = k * k;
+
+        Biquad::from_digital(
+            [
+                b[0] + b[1] * k + b[2] * k2,
+                2. * b[0] - 2. * b[2] * k2,
+                b[0] - b[1] * k + b[2] * k2,
+            ],
+            [
+                a[0] + a[1] * k + a[2] * k2,
+                2. * a[0] - 2. * a[2] * k2,
+                a[0] - a[1] * k + a[2] * k2,
+            ],
+        )
+    }
+
+    /// Creates the filter from the coefficients of the digital transfer function
+    /// `H(z) = (b[0] + b[1]z⁻¹ + b[2]z⁻²) / (a[0] + a[1]z⁻¹ + a[2]z⁻²)`
+    pub fn from_digital(b: [f64; 3], a: [f64; 3]) -> Biquad {
+        Biquad {
+            b0: b[0] / a[0],
+            b1: b[1] / a[0],
+            b2: b[2] / a[0],
+            a1: a[1] / a[0],
+            a2: a[2] / a[0],
+        }
+    }
+
+    /// Second order low pass filter with the cutoff `frequency` in hertz and quality factor `q`
+    pub fn lowpass(frequency: f64, q: f64, sampling_rate: u32) -> Biquad {
+        let (cos_w0, alpha) = cookbook_parameters(frequency, q, sampling_rate);
+
+        Biquad::from_digital(
+            [(1. - cos_w0) / 2., 1. - cos_w0, (1. - cos_w0) / 2.],
+            [1. + alpha, -2. * cos_w0, 1. - alpha],
+        )
+    }
+
+    /// Second order high pass filter with the cutoff `frequency` in hertz and quality factor `q`
+    pub fn highpass(frequency: f64, q: f64, sampling_rate: u32) -> Biquad {
+        let (cos_w0, alpha) = cookbook_parameters(frequency, q, sampling_rate);
+
+        Biquad::from_digital(
+            [(1. + cos_w0) / 2., -(1. + cos_w0), (1. + cos_w0) / 2.],
+            [1. + alpha, -2. * cos_w0, 1. - alpha],
+        )
+    }
+
+    /// The first stage of the K-weighting filter from ITU-R BS.1770, a high shelf of about +4dB
+    /// modelling the acoustic effect of the head. The coefficients for rates other than 48kHz are
+    /// derived from the analog parameters of the filter.
+    pub fn k_weighting_shelf(sampling_rate: u32) -> Biquad {
+        let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+
+        let k = (PI * f0 / sampling_rate as f64).tan();
+        let vh = 10f64.powf(gain_db / 20.);
+        let vb = vh.powf(0.4996667741545416);
+
+        Biquad::from_digital(
+            [
+                vh + vb * k / q + k * k,
+                2. * (k * k - vh),
+                vh - vb * k / q + k * k,
+            ],
+            [1. + k / q + k * k, 2. * (k * k - 1.), 1. - k / q + k * k],
+        )
+    }
+
+    /// The second stage of the K-weighting filter from ITU-R BS.1770, the "RLB" high pass filter
+    pub fn k_weighting_highpass(sampling_rate: u32) -> Biquad {
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+
+        let k = (PI * f0 / sampling_rate as f64).tan();
+
+        Biquad::from_digital(
+            [1., -2., 1.],
+            [1. + k / q + k * k, 2. * (k * k - 1.), 1. - k / q + k * k],
+        )
+    }
+
+    /// Pre-emphasis with a single time constant `tau` in seconds (e.g. 50µs or 75µs
+    /// as used for FM broadcasting). The gain is unity at DC and rises by 6dB per octave
+    /// above `1 / (2π tau)` hertz.
+    pub fn preemphasis(tau: f64, sampling_rate: u32) -> Biquad {
+        Biquad::from_analog([1., tau, 0.], [1., LIMITING_POLE, 0.], sampling_rate)
+    }
+
+    /// The RIAA recording curve, i.e. the inverse of the RIAA playback equalisation,
+    /// with the time constants 3180µs, 318µs and 75µs. The gain is normalised to 0dB at 1kHz.
+    pub fn riaa_preemphasis(sampling_rate: u32) -> Biquad {
+        let (t1, t2, t3) = (3180e-6, 318e-6, 75e-6);
+
+        // (1 + s t1)(1 + s t3) / ((1 + s t2)(1 + s LIMITING_POLE))
+        let mut biquad = Biquad::from_analog(
+            [1., t1 + t3, t1 * t3],
+            [1., t2 + LIMITING_POLE, t2 * LIMITING_POLE],
+            sampling_rate,
+        );
+
+        let gain = biquad.gain_at(1000., sampling_rate);
+        biquad.scale(1. / gain);
+        biquad
+    }
+
+    /// The magnitude of the filter response at `frequency` hertz
+    pub fn gain_at(&self, frequency: f64, sampling_rate: u32) -> f64 {
+        let w = 2. * PI * frequency / sampling_rate as f64;
+        let (c1, s1) = (w.cos(), -w.sin());
+        let (c2, s2) = ((2. * w).cos(), -(2. * w).sin());
+
+        let num_re = self.b0 + self.b1 * c1 + self.b2 * c2;
+        let num_im = self.b1 * s1 + self.b2 * s2;
+        let den_re = 1. + self.a1 * c1 + self.a2 * c2;
+        let den_im = self.a1 * s1 + self.a2 * s2;
+
+        ((num_re * num_re + num_im * num_im) / (den_re * den_re + den_im * den_im)).sqrt()
+    }
+
+    /// Runs the samples of one channel through the filter
+    pub fn filter(&self, samples: &mut [f64]) {
+        let mut state = [0f64; 4];
+        for sample in samples.iter_mut() {
+            *sample = self.step(*sample, &mut state);
+        }
+    }
+
+    /// Filters the sample `x` updating the filter `state` (x[n-1], x[n-2], y[n-1], y[n-2])
+    fn step(&self, x: f64, state: &mut [f64; 4]) -> f64 {
+        let [x1, x2, y1, y2] = *state;
+        let y = self.b0 * x + self.b1 * x1 + self.b2 * x2 - self.a1 * y1 - self.a2 * y2;
+        *state = [x, x1, y, y1];
+        y
+    }
+
+    /// Scales the gain of the filter by `gain`
+    pub fn scale(&mut self, gain: f64) {
+        self.b0 *= gain;
+        self.b1 *= gain;
+        self.b2 *= gain;
+    }
+}
+
+/// Quality factor for a Butterworth response
+pub const BUTTERWORTH_Q: f64 = std::f64::consts::FRAC_1_SQRT_2;
+
+/// An octave band with the frequencies in hertz
+pub struct OctaveBand {
+    pub centre: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// The octave bands centred on 1000Hz (31.25Hz to 16kHz) whose upper edge lies
+/// below the Nyquist frequency
+pub fn octave_bands(sampling_rate: u32) -> Vec<OctaveBand> {
+    let nyquist = sampling_rate as f64 / 2.;
+
+    (-5..=4)
+        .map(|k| {
+            let centre = 1000. * 2f64.powi(k);
+            OctaveBand {
+                centre,
+                lower: centre / 2f64.sqrt(),
+                upper: centre * 2f64.sqrt(),
+            }
+        })
+        .filter(|band| band.upper < nyquist)
+        .collect()
+}
+
+/// Returns `cos(w0)` and `alpha` as used by the formulae in the "Audio EQ Cookbook" (R. Bristow-Johnson)
+fn cookbook_parameters(frequency: f64, q: f64, sampling_rate: u32) -> (f64, f64) {
+    let w0 = 2. * PI * frequency / sampling_rate as f64;
+    (w0.cos(), w0.sin() / (2. * q))
+}
+
+/// Runs the interleaved samples in `data` through the `biquad` filter. Each channel
+/// is filtered independently. Samples that exceed the `i16` range are clipped.
+pub fn apply_biquad(data: &mut [i16], number_channels: u8, biquad: &Biquad) {
+    let number_channels = number_channels as usize;
+
+    // Filter state for each channel
+    let mut state = vec![[0f64; 4]; number_channels];
+
+    for (i, sample) in data.iter_mut().enumerate() {
+        let y = biquad.step(*sample as f64, &mut state[i % number_channels]);
+        *sample = y.round() as i16;
+    }
+}
+
+/// The largest magnitude of the comb filter feedback. At 1.0 the filter would never decay.
+pub const MAX_COMB_FEEDBACK: f32 = 0.99;
+
+/// Runs the interleaved samples in `data` through a feedback comb filter
+/// `y[n] = (1 - |g|) x[n] + g y[n - delay_samples]`. The input is scaled by `1 - |g|` so that
+/// the peaks of the response have unity gain. The `feedback` g is clamped to
+/// `MAX_COMB_FEEDBACK`. Each channel has its own delay line.
+pub fn apply_comb(data: &mut [i16], delay_samples: usize, feedback: f32, number_channels: u8) {
+    let number_channels = number_channels as usize;
+    let feedback = feedback.clamp(-MAX_COMB_FEEDBACK, MAX_COMB_FEEDBACK);
+    let input_gain = 1. - feedback.abs();
+
+    if delay_samples == 0 {
+        return;
+    }
+
+    // A circular delay line holding the last outputs for each channel
+    let mut delay_lines = vec![vec![0f32; delay_samples]; number_channels];
+
+    for (i, sample) in data.iter_mut().enumerate() {
+        let delay_line = &mut delay_lines[i % number_channels];
+        let position = (i / number_channels) % delay_samples;
+
+        let y = input_gain * *sample as f32 + feedback * delay_line[position];
+        delay_line[position] = y;
+        *sample = y.round() as i16;
+    }
+}
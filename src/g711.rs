@@ -0,0 +1,62 @@
+//! Companding of 16 bit samples to 8 bits with the μ-law and A-law encodings of ITU-T G.711
+//!
+//! The encoders follow the reference implementation: the sample is reduced to 14 bits (μ-law) or
+//! 13 bits (A-law), the segment of the logarithmic curve is looked up in a table of the segment
+//! ends and the four bits within the segment are taken from the top of the sample.
+
+/// The largest magnitude of a 14 bit sample that can be encoded with μ-law
+const MULAW_CLIP: i32 = 8159;
+
+/// Added to the magnitude of a 14 bit μ-law sample so that the segments start at a power of 2
+const MULAW_BIAS: i32 = 0x21;
+
+/// The largest biased 14 bit magnitude in each μ-law segment
+const MULAW_SEGMENT_ENDS: [i32; 8] = [0x3F, 0x7F, 0xFF, 0x1FF, 0x3FF, 0x7FF, 0xFFF, 0x1FFF];
+
+/// The largest 13 bit magnitude in each A-law segment
+const ALAW_SEGMENT_ENDS: [i32; 8] = [0x1F, 0x3F, 0x7F, 0xFF, 0x1FF, 0x3FF, 0x7FF, 0xFFF];
+
+/// Encodes the `sample` with μ-law. The bits of the code are inverted as required by G.711, so
+/// silence is `0xFF`.
+pub fn linear_to_mulaw(sample: i16) -> u8 {
+    let sample = sample as i32 >> 2;
+    let (magnitude, mask) = if sample < 0 {
+        (-sample, 0x7F)
+    } else {
+        (sample, 0xFF)
+    };
+    let biased = magnitude.min(MULAW_CLIP) + MULAW_BIAS;
+
+    let code = match segment(biased, &MULAW_SEGMENT_ENDS) {
+        Some(segment) => (segment << 4) | ((biased >> (segment + 1)) & 0xF),
+        None => 0x7F,
+    };
+    (code ^ mask) as u8
+}
+
+/// Encodes the `sample` with A-law. The even bits of the code are inverted as required by
+/// G.711, so silence is `0xD5`.
+pub fn linear_to_alaw(sample: i16) -> u8 {
+    let sample = sample as i32 >> 3;
+    let (magnitude, mask) = if sample < 0 {
+        (-sample - 1, 0x55)
+    } else {
+        (sample, 0xD5)
+    };
+
+    let code = match segment(magnitude, &ALAW_SEGMENT_ENDS) {
+        // The first two segments have the same step size
+        Some(segment) if segment < 2 => (segment << 4) | ((magnitude >> 1) & 0xF),
+        Some(segment) => (segment << 4) | ((magnitude >> segment) & 0xF),
+        None => 0x7F,
+    };
+    (code ^ mask) as u8
+}
+
+/// The segment that `magnitude` falls into, or `None` if it is beyond the last segment
+fn segment(magnitude: i32, segment_ends: &[i32; 8]) -> Option<i32> {
+    segment_ends
+        .iter()
+        .position(|end| magnitude <= *end)
+        .map(|segment| segment as i32)
+}
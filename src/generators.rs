@@ -0,0 +1,740 @@
+//! The wave form generators
+use std::f32::consts::PI;
+
+use crate::error::WavGenError;
+use crate::sine_table::{fixed_phase, SineTable};
+
+/// Represents an harmonic as a frequency and it's relative amplitude to other harmonics
+#[derive(Debug)]
+pub struct Harmonic {
+    pub frequency: f32, // In hertz
+    pub amplitude: f32,
+    pub phase: f32, // In degrees
+}
+
+/// What is done with a sample that is outside the range of `i16`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OnClip {
+    /// Limit the sample to the largest or smallest `i16`
+    Saturate,
+    /// Wrap the sample around to the other end of the range, as with a cast to `i16`
+    Wrap,
+    /// Fail with `WavGenError::SampleOutOfRange`
+    Error,
+}
+
+impl OnClip {
+    /// Converts `value` to a sample. The `index` of the sample is only used for the error.
+    pub fn sample(self, value: i32, index: usize) -> Result<i16, WavGenError> {
+        match self {
+            OnClip::Saturate => Ok(value.clamp(i16::MIN as i32, i16::MAX as i32) as i16),
+            OnClip::Wrap => Ok(value as i16),
+            OnClip::Error => {
+                i16::try_from(value).map_err(|_| WavGenError::SampleOutOfRange(value, index))
+            }
+        }
+    }
+}
+
+/// Generate a sine wave as a set of `i16` samples and returns this.
+///
+/// # Arguments
+/// * `frequency`- The frequency of the sine wave in hertz
+/// * `phase` - The phase of the sine wave at the first sample in radians
+/// * `number_samples` - the number of samples to be generated.
+///   The duration of the generated wave is the `number_samples/sampling_rate`.
+/// * `number_channels` - The number of channels
+/// * `volume`- The volume of the generated sine wave, at most `i16::MAX`
+/// * `sampling_rate`- The rate at which the wave wave is sampled, e.g 44100 hertz.
+///   The `sample_rate` and the `duration` determine the the size of `data`
+/// * `progress` - Called with the number of samples generated so far (see `report_progress`)
+pub fn gen_sine_wave(
+    frequency: f32,
+    phase: f32,
+    number_samples: u32,
+    number_channels: u8,
+    volume: u16,
+    sampling_rate: u32,
+    progress: &mut dyn FnMut(u32),
+) -> Result<Vec<i16>, WavGenError> {
+    check_volume(volume)?;
+    Ok(fill_samples(
+        number_samples,
+        number_channels,
+        progress,
+        sine_at(frequency, phase, volume, sampling_rate),
+    ))
+}
+
+/// The samples of a sine wave (see `gen_sine_wave`) for one channel without an end. The
+/// samples can be written as they are generated instead of being held in memory.
+pub fn sine_samples(
+    frequency: f32,
+    phase: f32,
+    volume: u16,
+    sampling_rate: u32,
+) -> impl Iterator<Item = i16> {
+    (0..).map(sine_at(frequency, phase, volume, sampling_rate))
+}
+
+/// The values of a sine wave from -1.0 to 1.0 for one channel without an end, before they
+/// are scaled by the volume (see `to_sample` and `to_float_sample`)
+pub fn sine_values(frequency: f32, phase: f32, sampling_rate: u32) -> impl Iterator<Item = f32> {
+    (0..).map(sine_value_at(frequency, phase, sampling_rate))
+}
+
+/// The sample of a sine wave at the sampling period `t`
+fn sine_at(
+    frequency: f32,
+    phase: f32,
+    volume: u16,
+    sampling_rate: u32,
+) -> impl Fn(u32) -> i16 + Sync {
+    let value_at = sine_value_at(frequency, phase, sampling_rate);
+    move |t: u32| to_sample(value_at(t), volume)
+}
+
+/// The value of a sine wave from -1.0 to 1.0 at the sampling period `t`
+fn sine_value_at(frequency: f32, phase: f32, sampling_rate: u32) -> impl Fn(u32) -> f32 + Sync {
+    move |t: u32| {
+        let radians = (t as f32 * 2. * PI * frequency) / sampling_rate as f32 + phase;
+        radians.sin()
+    }
+}
+
+/// The sample of a sine wave at the sampling period `t` with the sine from the lookup table
+fn table_sine_at(
+    frequency: f32,
+    phase: f32,
+    volume: u16,
+    sampling_rate: u32,
+) -> impl Fn(u32) -> i16 + Sync {
+    let table = SineTable::get();
+    let increment = fixed_phase(frequency as f64 / sampling_rate as f64);
+    let start = fixed_phase(phase as f64 / (2. * std::f64::consts::PI));
+    move |t: u32| {
+        // The phase wraps around at the end of each cycle
+        let phase = start.wrapping_add((t as u64).wrapping_mul(increment));
+        (table.sin(phase) * volume as f32) as i16
+    }
+}
+
+/// Generate a square wave as a set of `i16` samples and returns this.
+///
+/// # Arguments
+/// * `frequency`- The frequency of the square wave in hertz
+/// * `duty_cycle` - The fraction of each cycle for which the wave is `+volume`. For the rest
+///   of the cycle the wave is `-volume`
+/// * `number_samples` - the number of samples to be generated.
+///   The duration of the generated wave is the `number_samples/sampling_rate`.
+/// * `number_channels` - The number of channels
+/// * `volume`- The volume of the generated square wave, at most `i16::MAX`
+/// * `sampling_rate`- The rate at which the wave wave is sampled, e.g 44100 hertz.
+/// * `progress` - Called with the number of samples generated so far (see `report_progress`)
+pub fn gen_square_wave(
+    frequency: f32,
+    duty_cycle: f32,
+    number_samples: u32,
+    number_channels: u8,
+    volume: u16,
+    sampling_rate: u32,
+    progress: &mut dyn FnMut(u32),
+) -> Result<Vec<i16>, WavGenError> {
+    check_volume(volume)?;
+    Ok(fill_samples(
+        number_samples,
+        number_channels,
+        progress,
+        square_at(frequency, duty_cycle, volume, sampling_rate),
+    ))
+}
+
+/// The samples of a square wave (see `gen_square_wave`) for one channel without an end
+pub fn square_samples(
+    frequency: f32,
+    duty_cycle: f32,
+    volume: u16,
+    sampling_rate: u32,
+) -> impl Iterator<Item = i16> {
+    (0..).map(square_at(frequency, duty_cycle, volume, sampling_rate))
+}
+
+/// The values of a square wave, -1.0 or 1.0, for one channel without an end (see `sine_values`)
+pub fn square_values(
+    frequency: f32,
+    duty_cycle: f32,
+    sampling_rate: u32,
+) -> impl Iterator<Item = f32> {
+    (0..).map(square_value_at(frequency, duty_cycle, sampling_rate))
+}
+
+/// The sample of a square wave at the sampling period `t`
+fn square_at(
+    frequency: f32,
+    duty_cycle: f32,
+    volume: u16,
+    sampling_rate: u32,
+) -> impl Fn(u32) -> i16 + Sync {
+    let value_at = square_value_at(frequency, duty_cycle, sampling_rate);
+    move |t: u32| to_sample(value_at(t), volume)
+}
+
+/// The value of a square wave, -1.0 or 1.0, at the sampling period `t`
+fn square_value_at(
+    frequency: f32,
+    duty_cycle: f32,
+    sampling_rate: u32,
+) -> impl Fn(u32) -> f32 + Sync {
+    move |t: u32| {
+        let phase = (t as f32 * frequency / sampling_rate as f32) % 1.0;
+        if phase < duty_cycle {
+            1.
+        } else {
+            -1.
+        }
+    }
+}
+
+/// The duty cycle of a pulse wave that is modulated by a low frequency sine wave
+#[derive(Debug, Clone, Copy)]
+pub struct Pwm {
+    pub duty_cycle: f32, // The duty cycle without modulation, from 0.0 to 1.0
+    pub rate: f32,       // Frequency of the modulation in hertz
+    pub depth: f32,      // Largest change of the duty cycle
+}
+
+/// Generate a pulse wave whose duty cycle is modulated (pulse width modulation) as a set of
+/// `i16` samples and returns this. With a `depth` of 0 this is a square wave.
+///
+/// # Arguments
+/// * `frequency`- The frequency of the pulse wave in hertz
+/// * `pwm` - The duty cycle and its modulation. The duty cycle is limited to 0.0 to 1.0
+/// * `number_samples` - the number of samples to be generated.
+///   The duration of the generated wave is the `number_samples/sampling_rate`.
+/// * `number_channels` - The number of channels
+/// * `volume`- The volume of the generated pulse wave, at most `i16::MAX`
+/// * `sampling_rate`- The rate at which the wave wave is sampled, e.g 44100 hertz.
+/// * `progress` - Called with the number of samples generated so far (see `report_progress`)
+pub fn gen_pulse_wave(
+    frequency: f32,
+    pwm: Pwm,
+    number_samples: u32,
+    number_channels: u8,
+    volume: u16,
+    sampling_rate: u32,
+    progress: &mut dyn FnMut(u32),
+) -> Result<Vec<i16>, WavGenError> {
+    check_volume(volume)?;
+    let pulse_at = move |t: u32| {
+        let time = t as f32 / sampling_rate as f32;
+        let phase = (time * frequency) % 1.0;
+        let duty_cycle =
+            (pwm.duty_cycle + pwm.depth * (2. * PI * pwm.rate * time).sin()).clamp(0., 1.);
+        if phase < duty_cycle {
+            volume as i16
+        } else {
+            -(volume as i16)
+        }
+    };
+
+    Ok(fill_samples(
+        number_samples,
+        number_channels,
+        progress,
+        pulse_at,
+    ))
+}
+
+/// Generate a triangle wave as a set of `i16` samples and returns this. The wave ramps
+/// linearly from `-volume` up to `+volume` over the first half of each cycle and back down
+/// over the second half.
+///
+/// # Arguments
+/// * `frequency`- The frequency of the triangle wave in hertz
+/// * `number_samples` - the number of samples to be generated.
+///   The duration of the generated wave is the `number_samples/sampling_rate`.
+/// * `number_channels` - The number of channels
+/// * `volume`- The volume of the generated triangle wave, at most `i16::MAX`
+/// * `sampling_rate`- The rate at which the wave wave is sampled, e.g 44100 hertz.
+/// * `progress` - Called with the number of samples generated so far (see `report_progress`)
+pub fn gen_triangle_wave(
+    frequency: f32,
+    number_samples: u32,
+    number_channels: u8,
+    volume: u16,
+    sampling_rate: u32,
+    progress: &mut dyn FnMut(u32),
+) -> Result<Vec<i16>, WavGenError> {
+    check_volume(volume)?;
+    Ok(fill_samples(
+        number_samples,
+        number_channels,
+        progress,
+        triangle_at(frequency, volume, sampling_rate),
+    ))
+}
+
+/// The samples of a triangle wave (see `gen_triangle_wave`) for one channel without an end
+pub fn triangle_samples(
+    frequency: f32,
+    volume: u16,
+    sampling_rate: u32,
+) -> impl Iterator<Item = i16> {
+    (0..).map(triangle_at(frequency, volume, sampling_rate))
+}
+
+/// The values of a triangle wave from -1.0 to 1.0 for one channel without an end (see
+/// `sine_values`)
+pub fn triangle_values(frequency: f32, sampling_rate: u32) -> impl Iterator<Item = f32> {
+    (0..).map(triangle_value_at(frequency, sampling_rate))
+}
+
+/// The sample of a triangle wave at the sampling period `t`
+fn triangle_at(frequency: f32, volume: u16, sampling_rate: u32) -> impl Fn(u32) -> i16 + Sync {
+    let value_at = triangle_value_at(frequency, sampling_rate);
+    move |t: u32| to_sample(value_at(t), volume)
+}
+
+/// The value of a triangle wave from -1.0 to 1.0 at the sampling period `t`
+fn triangle_value_at(frequency: f32, sampling_rate: u32) -> impl Fn(u32) -> f32 + Sync {
+    move |t: u32| {
+        let phase = (t as f32 * frequency / sampling_rate as f32) % 1.0;
+        if phase < 0.5 {
+            4. * phase - 1.
+        } else {
+            3. - 4. * phase
+        }
+    }
+}
+
+/// Generate a sawtooth wave as a set of `i16` samples and returns this. The wave ramps
+/// linearly from `-volume` to `+volume` over each cycle.
+///
+/// # Arguments
+/// * `frequency`- The frequency of the sawtooth wave in hertz
+/// * `descending` - Ramp from `+volume` down to `-volume` instead
+/// * `number_samples` - the number of samples to be generated.
+///   The duration of the generated wave is the `number_samples/sampling_rate`.
+/// * `number_channels` - The number of channels
+/// * `volume`- The volume of the generated sawtooth wave, at most `i16::MAX`
+/// * `sampling_rate`- The rate at which the wave wave is sampled, e.g 44100 hertz.
+/// * `progress` - Called with the number of samples generated so far (see `report_progress`)
+pub fn gen_sawtooth_wave(
+    frequency: f32,
+    descending: bool,
+    number_samples: u32,
+    number_channels: u8,
+    volume: u16,
+    sampling_rate: u32,
+    progress: &mut dyn FnMut(u32),
+) -> Result<Vec<i16>, WavGenError> {
+    check_volume(volume)?;
+    Ok(fill_samples(
+        number_samples,
+        number_channels,
+        progress,
+        sawtooth_at(frequency, descending, volume, sampling_rate),
+    ))
+}
+
+/// The samples of a sawtooth wave (see `gen_sawtooth_wave`) for one channel without an end
+pub fn sawtooth_samples(
+    frequency: f32,
+    descending: bool,
+    volume: u16,
+    sampling_rate: u32,
+) -> impl Iterator<Item = i16> {
+    (0..).map(sawtooth_at(frequency, descending, volume, sampling_rate))
+}
+
+/// The values of a sawtooth wave from -1.0 to 1.0 for one channel without an end (see
+/// `sine_values`)
+pub fn sawtooth_values(
+    frequency: f32,
+    descending: bool,
+    sampling_rate: u32,
+) -> impl Iterator<Item = f32> {
+    (0..).map(sawtooth_value_at(frequency, descending, sampling_rate))
+}
+
+/// The sample of a sawtooth wave at the sampling period `t`
+fn sawtooth_at(
+    frequency: f32,
+    descending: bool,
+    volume: u16,
+    sampling_rate: u32,
+) -> impl Fn(u32) -> i16 + Sync {
+    let value_at = sawtooth_value_at(frequency, descending, sampling_rate);
+    move |t: u32| to_sample(value_at(t), volume)
+}
+
+/// The value of a sawtooth wave from -1.0 to 1.0 at the sampling period `t`
+fn sawtooth_value_at(
+    frequency: f32,
+    descending: bool,
+    sampling_rate: u32,
+) -> impl Fn(u32) -> f32 + Sync {
+    move |t: u32| {
+        let phase = (t as f32 * frequency / sampling_rate as f32) % 1.0;
+        let value = phase * 2.0 - 1.0;
+        if descending {
+            -value
+        } else {
+            value
+        }
+    }
+}
+
+/// Generate a sweeping sine wave as a set of `i16` samples and returns it. The frequency changes
+/// linearly from `start` to `finish` and the phase is accumulated so that the wave is continuous.
+///
+/// # Arguments
+/// * `start` - The start frequency of sweep in hertz
+/// * `finish`- The finishing frequency of the sweep in hertz
+/// * ´number_samples" - the number of samples to be generated.
+///   The duration of the generated wave is the `number_samples/sampling_rate`.
+/// * `number_channels` - The number of channels
+/// * `volume`- The volume of the generated wave, at most `i16::MAX`
+/// * `sampling_rate`- The rate at which the wave wave is sampled, e.g 44100 hertz.
+///   The `sample_rate` and the `duration` determine the the size of `data`
+/// * `progress` - Called with the number of samples generated so far (see `report_progress`)
+pub fn gen_sweep_wave(
+    start: u32,
+    finish: u32,
+    number_samples: u32,
+    number_channels: u8,
+    volume: u16,
+    sampling_rate: u32,
+    progress: &mut dyn FnMut(u32),
+) -> Result<Vec<i16>, WavGenError> {
+    check_volume(volume)?;
+    let frequency_increment: f32 = (finish as f32 - start as f32) / number_samples as f32;
+
+    gen_chirp(
+        |t| start as f32 + frequency_increment * t as f32,
+        number_samples,
+        number_channels,
+        volume,
+        sampling_rate,
+        progress,
+    )
+}
+
+/// Generate a sine wave whose frequency changes over time as a set of `i16` samples. The phase
+/// is accumulated from sample to sample so that the wave stays continuous however the frequency changes.
+///
+/// # Arguments
+/// * `frequency_at` - Gives the frequency in hertz for each sample
+/// * `number_samples` - the number of samples to be generated.
+/// * `number_channels` - The number of channels
+/// * `volume`- The volume of the generated wave, at most `i16::MAX`
+/// * `sampling_rate`- The rate at which the wave wave is sampled, e.g 44100 hertz.
+/// * `progress` - Called with the number of samples generated so far (see `report_progress`)
+pub fn gen_chirp(
+    frequency_at: impl Fn(u32) -> f32,
+    number_samples: u32,
+    number_channels: u8,
+    volume: u16,
+    sampling_rate: u32,
+    progress: &mut dyn FnMut(u32),
+) -> Result<Vec<i16>, WavGenError> {
+    check_volume(volume)?;
+    let mut data = Vec::<i16>::new();
+    let mut phase: f32 = 0.;
+
+    for t in 0..number_samples {
+        let amplitude = (phase.sin() * volume as f32) as i16;
+
+        for _ in 0..number_channels {
+            data.push(amplitude);
+        }
+
+        // Keep the phase small so that it does not loose precision
+        phase = (phase + 2. * PI * frequency_at(t) / sampling_rate as f32) % (2. * PI);
+        report_progress(t + 1, number_samples, progress);
+    }
+
+    Ok(data)
+}
+
+/// Generate the sum of a set of harmonics as a set of `i16` samples and returns this. Each
+/// harmonic is a sine wave with the volume scaled by its amplitude. A sum outside the `i16`
+/// range is handled as given by `on_clip`.
+///
+/// # Arguments
+/// * `harmonics_set` - The harmonics that make up the wave
+/// * `number_samples` - the number of samples to be generated.
+/// * `number_channels` - The number of channels
+/// * `volume`- The volume of a harmonic with an amplitude of 1, at most `i16::MAX`
+/// * `sampling_rate`- The rate at which the wave wave is sampled, e.g 44100 hertz.
+/// * `fast_sine` - Use a lookup table for the sine, which is faster but not quite as accurate
+/// * `on_clip` - What is done with the samples where the sum is outside the `i16` range
+/// * `progress` - Called with the number of samples generated so far (see `report_progress`)
+#[allow(clippy::too_many_arguments)]
+pub fn gen_harmonics(
+    harmonics_set: &[Harmonic],
+    number_samples: u32,
+    number_channels: u8,
+    volume: u16,
+    sampling_rate: u32,
+    fast_sine: bool,
+    on_clip: OnClip,
+    progress: &mut dyn FnMut(u32),
+) -> Result<Vec<i16>, WavGenError> {
+    if harmonics_set.is_empty() {
+        return Err(WavGenError::NoHarmonics);
+    }
+    check_volume(volume)?;
+
+    // The harmonics are summed as i32 so that the sum cannot overflow and then converted to i16
+    let mut sum = vec![0i32; (number_samples * number_channels as u32) as usize];
+    for (i, harmonic_entry) in harmonics_set.iter().enumerate() {
+        // Each harmonic is an equal part of the progress
+        let mut harmonic_progress = |samples_done: u32| {
+            let total = i as u64 * number_samples as u64 + samples_done as u64;
+            progress((total / harmonics_set.len() as u64) as u32);
+        };
+        let (frequency, phase, volume) = (
+            harmonic_entry.frequency,
+            harmonic_entry.phase.to_radians(),
+            (harmonic_entry.amplitude * volume as f32) as u16,
+        );
+        if fast_sine {
+            let sample_at = table_sine_at(frequency, phase, volume, sampling_rate);
+            add_samples(&mut sum, number_channels, &mut harmonic_progress, sample_at);
+        } else {
+            let sample_at = sine_at(frequency, phase, volume, sampling_rate);
+            add_samples(&mut sum, number_channels, &mut harmonic_progress, sample_at);
+        }
+    }
+
+    sum.into_iter()
+        .enumerate()
+        .map(|(i, total)| on_clip.sample(total, i))
+        .collect()
+}
+
+/// Generates `number_samples` samples with `sample_at` for each sampling period and writes each
+/// of them to all the channels
+#[cfg(not(feature = "parallel"))]
+fn fill_samples(
+    number_samples: u32,
+    number_channels: u8,
+    progress: &mut dyn FnMut(u32),
+    sample_at: impl Fn(u32) -> i16 + Sync,
+) -> Vec<i16> {
+    let mut data = Vec::<i16>::new();
+
+    for t in 0..number_samples {
+        let amplitude = sample_at(t);
+
+        // Data consists of a sample for each channel in turn. As all the channels are the same,
+        // the same sample is written for each channel.
+        for _ in 0..number_channels {
+            data.push(amplitude);
+        }
+        report_progress(t + 1, number_samples, progress);
+    }
+
+    data
+}
+
+/// Adds the sample from `sample_at` for each sampling period to all the channels of `sum`
+#[cfg(not(feature = "parallel"))]
+fn add_samples(
+    sum: &mut [i32],
+    number_channels: u8,
+    progress: &mut dyn FnMut(u32),
+    sample_at: impl Fn(u32) -> i16 + Sync,
+) {
+    let number_samples = (sum.len() / number_channels as usize) as u32;
+
+    for (t, frame) in (0..).zip(sum.chunks_mut(number_channels as usize)) {
+        let amplitude = sample_at(t) as i32;
+        for total in frame.iter_mut() {
+            *total += amplitude;
+        }
+        report_progress(t + 1, number_samples, progress);
+    }
+}
+
+/// The number of sampling periods that are generated in parallel before the progress is reported
+#[cfg(feature = "parallel")]
+const PARALLEL_BLOCK: u32 = 256 * PROGRESS_INTERVAL;
+
+/// Generates `number_samples` samples with `sample_at` for each sampling period and writes each
+/// of them to all the channels. The samples are generated in parallel, `PARALLEL_BLOCK` at a time.
+#[cfg(feature = "parallel")]
+fn fill_samples(
+    number_samples: u32,
+    number_channels: u8,
+    progress: &mut dyn FnMut(u32),
+    sample_at: impl Fn(u32) -> i16 + Sync,
+) -> Vec<i16> {
+    use rayon::prelude::*;
+
+    let number_channels = number_channels as usize;
+    let mut data = vec![0i16; number_samples as usize * number_channels];
+
+    let block_size = PARALLEL_BLOCK as usize * number_channels;
+    for (block, block_data) in (0..).zip(data.chunks_mut(block_size)) {
+        let start = block * PARALLEL_BLOCK;
+        block_data
+            .par_chunks_mut(number_channels)
+            .enumerate()
+            .for_each(|(i, frame)| frame.fill(sample_at(start + i as u32)));
+
+        let samples_done = start + (block_data.len() / number_channels) as u32;
+        report_progress(samples_done, number_samples, progress);
+    }
+
+    data
+}
+
+/// Adds the sample from `sample_at` for each sampling period to all the channels of `sum`. The
+/// samples are generated in parallel, `PARALLEL_BLOCK` at a time.
+#[cfg(feature = "parallel")]
+fn add_samples(
+    sum: &mut [i32],
+    number_channels: u8,
+    progress: &mut dyn FnMut(u32),
+    sample_at: impl Fn(u32) -> i16 + Sync,
+) {
+    use rayon::prelude::*;
+
+    let number_channels = number_channels as usize;
+    let number_samples = (sum.len() / number_channels) as u32;
+
+    let block_size = PARALLEL_BLOCK as usize * number_channels;
+    for (block, block_sum) in (0..).zip(sum.chunks_mut(block_size)) {
+        let start = block * PARALLEL_BLOCK;
+        block_sum
+            .par_chunks_mut(number_channels)
+            .enumerate()
+            .for_each(|(i, frame)| {
+                let amplitude = sample_at(start + i as u32) as i32;
+                for total in frame.iter_mut() {
+                    *total += amplitude;
+                }
+            });
+
+        let samples_done = start + (block_sum.len() / number_channels) as u32;
+        report_progress(samples_done, number_samples, progress);
+    }
+}
+
+/// Scales a `value` of a wave from -1.0 to 1.0 by the `volume` to a 16 bit sample
+pub fn to_sample(value: f32, volume: u16) -> i16 {
+    (value * volume as f32) as i16
+}
+
+/// The 16 bit sample that is 1.0 as a floating point sample
+pub const FLOAT_SCALE: f32 = 65535.;
+
+/// Scales a `value` of a wave from -1.0 to 1.0 by `volume / FLOAT_SCALE` to a floating point sample.
+/// Unlike a 16 bit sample this keeps the full resolution of the `value`.
+pub fn to_float_sample(value: f32, volume: u16) -> f32 {
+    value * volume as f32 / FLOAT_SCALE
+}
+
+/// Checks that the samples of a wave with `volume` fit into an `i16`
+fn check_volume(volume: u16) -> Result<(), WavGenError> {
+    if volume > i16::MAX as u16 {
+        Err(WavGenError::Clipping(volume))
+    } else {
+        Ok(())
+    }
+}
+
+/// The number of samples between the calls of the progress callback of the generators
+pub const PROGRESS_INTERVAL: u32 = 4096;
+
+/// Calls `progress` with the number of samples generated so far every `PROGRESS_INTERVAL`
+/// samples and once all the `number_samples` have been generated
+pub fn report_progress(samples_done: u32, number_samples: u32, progress: &mut dyn FnMut(u32)) {
+    if samples_done.is_multiple_of(PROGRESS_INTERVAL) || samples_done == number_samples {
+        progress(samples_done);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sweep_has_no_phase_jumps() {
+        let sampling_rate = 44100;
+        let volume = 10000;
+        let finish = 2000;
+        let data =
+            gen_sweep_wave(100, finish, 44100, 1, volume, sampling_rate, &mut |_| {}).unwrap();
+
+        // A continuous sine changes by at most 2π f / rate of the volume from one sample to the next
+        let max_step = 2. * PI * finish as f32 / sampling_rate as f32 * volume as f32;
+        for pair in data.windows(2) {
+            let step = (pair[1] as f32 - pair[0] as f32).abs();
+            assert!(step <= max_step + 1., "jump of {} between samples", step);
+        }
+    }
+
+    /// Two harmonics in phase whose sum at the peak is twice the volume
+    fn loud_harmonics() -> Vec<Harmonic> {
+        (0..2)
+            .map(|_| Harmonic {
+                frequency: 100.,
+                amplitude: 1.,
+                phase: 90.,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn harmonics_above_full_scale_saturate() {
+        let data = gen_harmonics(
+            &loud_harmonics(),
+            100,
+            1,
+            30000,
+            44100,
+            false,
+            OnClip::Saturate,
+            &mut |_| {},
+        )
+        .unwrap();
+        assert_eq!(data[0], i16::MAX);
+    }
+
+    #[test]
+    fn harmonics_above_full_scale_wrap() {
+        let data = gen_harmonics(
+            &loud_harmonics(),
+            100,
+            1,
+            30000,
+            44100,
+            false,
+            OnClip::Wrap,
+            &mut |_| {},
+        )
+        .unwrap();
+        assert_eq!(data[0], 60000i32 as i16);
+    }
+
+    #[test]
+    fn harmonics_above_full_scale_error() {
+        let result = gen_harmonics(
+            &loud_harmonics(),
+            100,
+            1,
+            30000,
+            44100,
+            false,
+            OnClip::Error,
+            &mut |_| {},
+        );
+        assert!(matches!(
+            result,
+            Err(WavGenError::SampleOutOfRange(60000, 0))
+        ));
+    }
+}
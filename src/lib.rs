@@ -0,0 +1,31 @@
+//! The wave form generators of `wav-gen` as a library so that they can be used directly from
+//! other rust programs, e.g. in a test suite.
+//!
+//! The generators return the samples as `i16`, or `WavGenError::Clipping` if the volume is too
+//! large for the samples to fit. For two channels the samples are interleaved,
+//! i.e. the left channel sample is followed by the right channel sample. The progress of the
+//! generation is reported to a callback with the number of samples generated so far.
+//!
+//! The basic wave forms are also available as endless iterators of the samples of one channel,
+//! e.g. `sine_samples`, so that long waves can be written without holding them in memory. The
+//! values of these wave forms from -1.0 to 1.0, e.g. `sine_values`, can be scaled by the volume to
+//! 16 bit samples with `to_sample` or to floating point samples with `to_float_sample`.
+//!
+//! ```
+//! use wav_gen::gen_sine_wave;
+//!
+//! // One second of a 440 hertz mono sine wave sampled at 44100 hertz
+//! let samples = gen_sine_wave(440., 0., 44100, 1, i16::MAX as u16, 44100, &mut |_| {})?;
+//! # Ok::<(), wav_gen::WavGenError>(())
+//! ```
+pub mod error;
+mod generators;
+mod sine_table;
+
+pub use error::WavGenError;
+pub use generators::{
+    gen_chirp, gen_harmonics, gen_pulse_wave, gen_sawtooth_wave, gen_sine_wave, gen_square_wave,
+    gen_sweep_wave, gen_triangle_wave, report_progress, sawtooth_samples, sawtooth_values,
+    sine_samples, sine_values, square_samples, square_values, to_float_sample, to_sample,
+    triangle_samples, triangle_values, Harmonic, OnClip, Pwm, FLOAT_SCALE, PROGRESS_INTERVAL,
+};
@@ -0,0 +1,111 @@
+//! Loudness measurement as specified in ITU-R BS.1770-4
+use crate::filter::Biquad;
+
+/// Length of a gating block in milliseconds
+const BLOCK_MS: u32 = 400;
+
+/// Step between the gating blocks in milliseconds, i.e. the blocks overlap by 75%
+const STEP_MS: u32 = 100;
+
+/// Blocks quieter than this (in LUFS) are not used
+const ABSOLUTE_GATE: f64 = -70.;
+
+/// Blocks quieter than the loudness of the absolute gated blocks by this amount (in LU) are not used
+const RELATIVE_GATE: f64 = -10.;
+
+/// Measures the integrated loudness of the interleaved samples in `data` in LUFS.
+///
+/// Each channel is K-weighted and the mean square is taken over blocks of 400ms.
+/// The blocks are then gated, first with an absolute gate of -70 LUFS and then with
+/// a gate 10 LU below the loudness of the remaining blocks. All channels are weighted
+/// equally, which is correct for mono and stereo.
+///
+/// Returns `None` if the wave is shorter than one block, the sampling rate is too low for the steps
+/// between the blocks or all the blocks are gated, e.g. for silence.
+pub fn integrated_loudness(data: &[i16], number_channels: u8, sampling_rate: u32) -> Option<f64> {
+    let number_channels = number_channels as usize;
+    let shelf = Biquad::k_weighting_shelf(sampling_rate);
+    let highpass = Biquad::k_weighting_highpass(sampling_rate);
+
+    // K-weight each channel normalised to full scale
+    let channels: Vec<Vec<f64>> = (0..number_channels)
+        .map(|c| {
+            let mut samples: Vec<f64> = data
+                .iter()
+                .skip(c)
+                .step_by(number_channels)
+                .map(|s| *s as f64 / 32768.)
+                .collect();
+            shelf.filter(&mut samples);
+            highpass.filter(&mut samples);
+            samples
+        })
+        .collect();
+
+    let number_frames = data.len() / number_channels;
+    let block_length = (BLOCK_MS as u64 * sampling_rate as u64 / 1000) as usize;
+    let step = (STEP_MS as u64 * sampling_rate as u64 / 1000) as usize;
+    // At sampling rates below 10 hertz there is not a sample in each step
+    if block_length == 0 || step == 0 || number_frames < block_length {
+        return None;
+    }
+
+    // Sum over the channels of the mean square of each block
+    let block_powers: Vec<f64> = (0..=(number_frames - block_length) / step)
+        .map(|j| {
+            channels
+                .iter()
+                .map(|samples| {
+                    let block = &samples[j * step..j * step + block_length];
+                    block.iter().map(|s| s * s).sum::<f64>() / block_length as f64
+                })
+                .sum()
+        })
+        .collect();
+
+    let absolute_gated: Vec<f64> = block_powers
+        .iter()
+        .copied()
+        .filter(|p| loudness(*p) > ABSOLUTE_GATE)
+        .collect();
+    if absolute_gated.is_empty() {
+        return None;
+    }
+
+    let relative_gate = loudness(mean(&absolute_gated)) + RELATIVE_GATE;
+    let relative_gated: Vec<f64> = absolute_gated
+        .iter()
+        .copied()
+        .filter(|p| loudness(*p) > relative_gate)
+        .collect();
+    if relative_gated.is_empty() {
+        return None;
+    }
+
+    Some(loudness(mean(&relative_gated)))
+}
+
+/// Converts the power of a block to loudness in LUFS
+fn loudness(power: f64) -> f64 {
+    -0.691 + 10. * power.log10()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loudness_of_sampling_rate_below_one_step() {
+        // A step of 100ms is less than one sample at 5 hertz
+        assert_eq!(integrated_loudness(&[1000; 10], 1, 5), None);
+    }
+
+    #[test]
+    fn loudness_of_silence() {
+        assert_eq!(integrated_loudness(&vec![0; 2 * 48000], 2, 48000), None);
+    }
+}
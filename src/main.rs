@@ -18,6 +18,97 @@
 //! ```
 //! Note: it is assumed throughout that a `wav-gen` alias has been crated for the executable `wav-gen.exe`
 //!
+//! The duration is in seconds and can include a fraction, e.g. `--duration 2.5`. For short waves the duration can
+//! be given in milliseconds instead, e.g. `--duration-ms 20`.
+//!
+//! The sine wave can start with a phase offset in degrees, e.g. for a quadrature signal use `--phase 90`:
+//!
+//! ```console
+//!  wav-gen wav sine --frequency 643 --phase 90 cosine.wav
+//! ```
+//!
+//! Instead of the frequency, the note can be given by its name, e.g. `A4`, `C#5` or `Bb3`, in equal temperament
+//! with A4 as 440 hertz:
+//!
+//! ```console
+//!  wav-gen wav sine --note C#5 note.wav
+//! ```
+//!
+//! A vibrato can be added to the sine wave with `--vibrato-rate` in hertz. The depth of the vibrato is given in cents
+//! with `--vibrato-depth` (50 cents if not given):
+//!
+//! ```console
+//!  wav-gen wav sine --note A4 --vibrato-rate 6 --vibrato-depth 30 vibrato.wav
+//! ```
+//!
+//! For binaural test tones the right channel can have a different frequency to the left channel:
+//!
+//! ```console
+//!  wav-gen wav sine --frequency 440 --frequency-right 444 binaural.wav
+//! ```
+//!
+//! The waves are sampled at 44100 hertz. A different sampling rate, e.g. for an embedded DAC, can be set
+//! with `--sampling-rate`:
+//!
+//! ```console
+//!  wav-gen wav sine --frequency 643 --sampling-rate 8000 sine.wav
+//! ```
+//!
+//! The volume can also be given in dB relative to full scale with `--gain-db`, so 0 dB is the largest 16 bit
+//! sample value of 32767. A larger volume would clip the wave and is reduced to 32767 with a warning:
+//!
+//! ```console
+//!  wav-gen wav sine --frequency 643 --gain-db -6 sine.wav
+//! ```
+//!
+//! Or as a fraction of full scale from 0.0 to 1.0 with `--amplitude`, e.g. half of full scale:
+//!
+//! ```console
+//!  wav-gen wav sine --frequency 643 --amplitude 0.5 sine.wav
+//! ```
+//!
+//! ## Square Wave
+//!
+//! To generate a **square wave** of 440 hertz that is high for a quarter of each cycle:
+//!
+//! ```console
+//! wav-gen wav square --frequency 440 --duty-cycle 0.25 square.wav
+//! ```
+//! Without `--duty-cycle` the wave is high for half of each cycle.
+//!
+//! ## Pulse Wave
+//!
+//! A **pulse wave** is a square wave whose duty cycle can be swept by a low frequency sine wave, giving the
+//! classic pulse width modulation (PWM) synthesiser sound. To sweep the duty cycle between 0.2 and 0.8 twice a
+//! second:
+//!
+//! ```console
+//! wav-gen wav pulse --frequency 110 --duty 0.5 --pwm-rate 2 --pwm-depth 0.3 pwm.wav
+//! ```
+//! Without `--pwm-rate` the duty cycle stays at `--duty` as for a square wave.
+//!
+//! ## Triangle Wave
+//!
+//! A **triangle wave** that ramps up from `-volume` to `volume` and back down again in each cycle is generated with:
+//!
+//! ```console
+//! wav-gen wav triangle --frequency 440 triangle.wav
+//! ```
+//!
+//! ## Sawtooth Wave
+//!
+//! A **sawtooth wave** ramps up from `-volume` to `volume` in each cycle. With `--descending` it ramps down instead:
+//!
+//! ```console
+//! wav-gen wav sawtooth --frequency 440 --descending sawtooth.wav
+//! ```
+//! The harmonics of the jump in each cycle extend above the Nyquist frequency and alias, which is audible at high
+//! frequencies. With `--band-limited` the wave is summed from its harmonics below the Nyquist frequency instead:
+//!
+//! ```console
+//! wav-gen wav sawtooth --frequency 55 --band-limited bass.wav
+//! ```
+//!
 //! ## Sweeping Sine Wave
 //!
 //! To generate a sine wave that:
@@ -28,6 +119,23 @@
 //! ```console
 //! wav-gen wav sweep --start 300  --finish 1000 --duration 5 sweep.wav
 //! ```
+//! To sweep up to the finishing frequency and back down again in the same file use `--sweep-return`:
+//!
+//! ```console
+//! wav-gen wav sweep --start 300  --finish 1000 --duration 5 --sweep-return sweep.wav
+//! ```
+//! The start frequency can be held as a steady tone before the sweep starts, and the last frequency after it ends, with
+//! `--pre-hold-ms` and `--post-hold-ms`. The holds are added to the duration:
+//!
+//! ```console
+//! wav-gen wav sweep --start 300  --finish 1000 --duration 5 --pre-hold-ms 500 --post-hold-ms 250 sweep.wav
+//! ```
+//! By default the frequency changes linearly. For impulse response measurements the frequency can be swept
+//! exponentially with `--log` so that each octave takes the same time:
+//!
+//! ```console
+//! wav-gen wav sweep --start 20  --finish 20000 --duration 10 --log sweep.wav
+//! ```
 //! ## Harmonics
 //!
 //! To generate a wave that has a set of harmonics first define the harmonics using a csv file (for example `harmonics.csv`):
@@ -41,11 +149,169 @@
 //! This specifies a wave with harmonics at 500Hz, 700Hz and 750Hz with respective amplitudes 0.3, 0.2 and 0.1.
 //! The amplitudes will be normalised.
 //!
+//! An optional third column gives the phase of each harmonic in degrees, e.g. for a band limited triangle wave
+//! from the odd harmonics with alternating phases:
+//!
+//! ```text
+//! frequency,amplitude,phase
+//! 100.0 , 1.0 , 0
+//! 300.0 , 0.111 , 180
+//! 500.0 , 0.04 , 0
+//! ```
+//!
+//! Blank lines and lines starting with `#` are ignored so that the file can be annotated:
+//!
+//! ```text
+//! # A major triad
+//! frequency,amplitude
+//!
+//! # Root and third
+//! 440.0 , 0.3
+//! 554.4 , 0.2
+//! # Fifth
+//! 659.3 , 0.2
+//! ```
+//!
 //! Then use:
 //!
 //! ```console
 //! wav-gen wav harmonics --infile harmonics.csv output_wave_file.wav
 //! ```
+//! With `--no-normalize` the amplitudes are used as they are, so an amplitude of 1.0 has the full volume. It is
+//! then up to you to avoid clipping, for instance with `--limiter`:
+//!
+//! ```console
+//! wav-gen wav harmonics --infile harmonics.csv --no-normalize output_wave_file.wav
+//! ```
+//! With `--amplitude-db` the amplitudes are given in dB relative to full scale, e.g. a harmonic at -6 has half
+//! the amplitude of one at 0:
+//!
+//! ```console
+//! wav-gen wav harmonics --infile harmonics_db.csv --amplitude-db output_wave_file.wav
+//! ```
+//! Long waves with many harmonics are generated faster with `--fast-sine`, which takes the sine of each harmonic
+//! from a lookup table instead of calculating it. The error of the table is below 1e-6, far below the step
+//! between 16 bit samples:
+//!
+//! ```console
+//! wav-gen wav harmonics --infile harmonics.csv --duration 600 --fast-sine output_wave_file.wav
+//! ```
+//! With `--infile -` the harmonics are read from stdin, e.g.:
+//!
+//! ```console
+//! generate_harmonics.py | wav-gen wav harmonics --infile - output_wave_file.wav
+//! ```
+//! ## Looping
+//!
+//! With `--loop` only one exact period of a sine, square, triangle or harmonics wave is generated and it is then
+//! repeated for the duration. This is faster for long waves and the wave is exactly periodic, without any drift of
+//! the phase. The frequencies need to be whole numbers of hertz. For 440 hertz at 44100 hertz the period is
+//! 2205 samples, i.e. 22 cycles:
+//!
+//! ```console
+//! wav-gen wav harmonics --infile harmonics.csv --duration 600 --loop harmonics.wav
+//! ```
+//!
+//! ## Bit Depths
+//!
+//! By default wav files have 16 bit samples. A different bit depth of 8, 16 or 24 can be selected with `--bit-depth`:
+//!
+//! ```console
+//! wav-gen wav sine --bit-depth 24 sine.wav
+//! ```
+//! 8 bit samples are unsigned and centred on 128 as required by the wav format.
+//!
+//! Reducing quiet waves to 8 bits gives audible distortion. With `--dither` triangular (TPDF) dither is added
+//! before the samples are reduced so that the error is a steady noise instead. Use `--seed` to make the dither
+//! reproducible:
+//!
+//! ```console
+//! wav-gen wav sine --bit-depth 8 --volume 500 --dither --seed 1 sine.wav
+//! ```
+//!
+//! For DSP pipelines that expect floating point samples from -1.0 to 1.0 use `--format float`:
+//!
+//! ```console
+//! wav-gen wav sine --format float sine.wav
+//! ```
+//! The float samples are the wave scaled by `volume / 65535`. For the waves that are written while they are
+//! generated (see [Progress](#progress)) the float samples are calculated directly, so they have the full 32 bit
+//! resolution. Otherwise they are converted from the 16 bit samples.
+//!
+//! To compare the same wave at different bit depths use `--bit-depths`
+//! with a comma separated list of 8, 16 or 24:
+//!
+//! ```console
+//! wav-gen wav sine --bit-depths 8,16,24 sine.wav
+//! ```
+//! This writes the files `sine_8bit.wav`, `sine_16bit.wav` and `sine_24bit.wav`.
+//!
+//! ## Chords
+//!
+//! A chord of notes with equal amplitudes is generated from a comma separated list of note names:
+//!
+//! ```console
+//! wav-gen wav chord --notes C4,E4,G4 chord.wav
+//! ```
+//! As with the harmonics the amplitudes are normalised so that the peak of the chord does not exceed the volume.
+//!
+//! ## DTMF
+//!
+//! The DTMF (touch tone) signal of a telephone key (0-9, `*`, `#` and A-D) is the sum of the sine waves of its row
+//! and column frequencies (ITU-T Q.23):
+//!
+//! ```console
+//! wav-gen wav dtmf --digit 5 --duration 0.1 dtmf_5.wav
+//! ```
+//!
+//! ## Binaural Beats
+//!
+//! Binaural beats have a sine wave of the carrier frequency in the left channel and a sine wave of the carrier plus
+//! the beat frequency in the right channel. The channels are generated independently and have to be stereo:
+//!
+//! ```console
+//! wav-gen wav binaural --carrier 200 --beat 10 --duration 600 binaural.wav
+//! ```
+//!
+//! ## Partials
+//!
+//! Instead of defining the harmonics in a file, a tone can be generated from the partials of a fundamental
+//! frequency, where by default the amplitude of the nth partial is 1/n:
+//!
+//! ```console
+//! wav-gen wav partials --fundamental 110 --partials 12 --inharmonicity 0.0004 piano.wav
+//! ```
+//! The inharmonicity coefficient `B` stretches the partials like those of a piano string, with the frequency of the nth
+//! partial being `f0 * n * sqrt(1 + B * n^2)`. The resulting partial frequencies are reported.
+//!
+//! With `--rolloff` the amplitudes fall as `1/n` (the default), as `1/n^2` or stay `flat`:
+//!
+//! ```console
+//! wav-gen wav partials --fundamental 220 --partials 20 --rolloff 1/n^2 soft.wav
+//! ```
+//!
+//! ## Wavetables
+//!
+//! A wavetable for a wavetable synthesiser can be generated as a set of single cycle frames that morph from
+//! one spectrum to another:
+//!
+//! ```console
+//! wav-gen wav wavetable --frames 64 --frame-size 2048 --from sine --to saw wavetable.wav
+//! ```
+//! The frames are concatenated in the output and the frame size must be a power of two from 4 to 65536. The spectra are `sine`,
+//! `saw` and `square`.
+//!
+//! For systems that need big-endian files, a RIFX file can be written with `--rifx`.
+//!
+//! Wav files are limited to 4GB. Larger waves, e.g. long multichannel waves, are written as RF64 (BW64) files
+//! which hold the sizes in a `ds64` chunk. An RF64 file can also be requested with `--rf64`.
+//!
+//! To play the wave directly, the wav file can be written to stdout by giving `-` as the output file name:
+//!
+//! ```console
+//! wav-gen wav sine --frequency 440 - | aplay
+//! ```
+//!
 //! ## Rust Data Arrays
 //!
 //! To generate a sine waveform of 500Hz as a rust data array of 44100 words use the following
@@ -57,10 +323,16 @@
 //! The generated rust source code file looks like:
 //!
 //! ```
+//! pub const DATA_SAMPLE_RATE: u32 = 44100;
+//! pub const DATA_CHANNELS: u8 = 2;
+//!
 //! pub static DATA: [i16; 1024] = [
 //!    // i16 values
 //! ];
 //! ```
+//! The constants give the sample rate and the number of channels so that the data can be played back correctly.
+//! Their names start with the name of the data structure.
+//!
 //! The i16 values alternate between the left channel first and then the right channel. For stereo, each channel has the same value.
 //!
 //! If the structure has been, for instance, generated in the file `wave.rs` then it can be imported with:
@@ -78,6 +350,46 @@
 //!
 //! This works the same for the other wave types such as `sweep` and `harmonics`.
 //!
+//! Up to 8 channels, each with the same samples, can be generated with `--channels`. The length needs to be a
+//! multiple of the number of channels:
+//!
+//! ```console
+//! wav-gen rust sine --frequency 500 --length 1024 --channels 4 ./wave_quad.rs
+//! ```
+//!
+//! For embedded code that places the table in flash, the data structure can be a `const` instead of a `static`
+//! with `--const`:
+//!
+//! ```
+//! pub const DATA: [i16; 1024] = [
+//!    // i16 values
+//! ];
+//! ```
+//!
+//! For drivers that expect a buffer for each channel, stereo data can be split into the arrays `DATA_LEFT` and
+//! `DATA_RIGHT` with `--split`:
+//!
+//! ```console
+//! wav-gen rust sine --frequency 500 --length 1024 --split ./wave_split.rs
+//! ```
+//!
+//! For DACs that expect unsigned samples centred at midscale, `--unsigned` adds 32768 to each sample and the
+//! data structure is then a `[u16; 1024]`. This also works for the C header and raw output:
+//!
+//! ```console
+//! wav-gen rust sine --frequency 500 --length 1024 --unsigned ./wave_dac.rs
+//! ```
+//!
+//! DACs with fewer bits, e.g. the 12 bit DACs of many microcontrollers, can be driven directly from the table
+//! with `--dac-bits`. The samples are scaled down to unsigned values of that many bits, i.e. from 0 to 4095
+//! centred at 2048 for 12 bits:
+//!
+//! ```console
+//! wav-gen rust sine --frequency 500 --length 1024 --dac-bits 12 ./wave_dac12.rs
+//! ```
+//!
+//! The number of samples on each line can be changed from 10 with `--columns`, e.g. `--columns 16`.
+//!
 //! A different name for the rust data structure can be specified with `--name`:
 //! ```console
 //! wav-gen rust sweep --start 500 --finish 1500 --name SWEEP_DATA ./sweep.rs
@@ -86,6 +398,9 @@
 //! The generated rust source code looks like:
 //!
 //! ```
+//! pub const SWEEP_DATA_SAMPLE_RATE: u32 = 44100;
+//! pub const SWEEP_DATA_CHANNELS: u8 = 2;
+//!
 //! pub static SWEEP_DATA: [i16; 1024] = [
 //!          0,     0,    71,    71,   143,   143,   214,   214,   285,   285,
 //!        355,   355,   423,   423,   490,   490,   554,   554,   616,   616,
@@ -94,6 +409,24 @@
 //! ];
 //! ```
 //!
+//! The sample rate and the number of channels can instead be generated as constants without the name with `--constants`:
+//!
+//! ```
+//! pub const SAMPLE_RATE: u32 = 44100;
+//! pub const CHANNELS: u8 = 2;
+//!
+//! pub static DATA: [i16; 1024] = [
+//!    // i16 values
+//! ];
+//! ```
+//!
+//! To audition exactly the same data as in the rust data structure, a wav file can also be written with `--also-wav`:
+//!
+//! ```console
+//! wav-gen rust sine --frequency 500 --length 44100 --also-wav ./wave.wav ./wave.rs
+//! ```
+//! In the same way `--also-rust` writes a rust data structure together with a wav file.
+//!
 //! For sine waves and harmonics, instead of generating a rust source code file with a large number of samples, only one cycle can be generated
 //! by using the `--cycle` flag, e.g.:
 //!  
@@ -102,473 +435,5114 @@
 //! wav-gen rust sine --frequency 2000 --cycle  ./src/SINE_DATA.rs
 //! ```
 //!
-//! # More options
-//! For more options use:
+//! An existing wav file, e.g. a recording, can be converted to a rust data structure with `convert`. Samples
+//! with a bit depth of 8, 24 or 32 (float) bits are converted to 16 bits:
 //!
 //! ```console
-//! wav-gen help
+//! wav-gen convert --infile recording.wav --name RECORDING ./recording.rs
 //! ```
-
-//  Wav format specification: see http://soundfile.sapp.org/doc/WaveFormat/
-
-use num::integer::lcm;
-use std::error::Error;
-use std::f32::consts::PI;
-use std::fs::File;
-use std::io::{BufWriter, Write};
-use std::path::Path;
-
-use wav::Header;
-
-use clap::{Args, CommandFactory, ErrorKind, Parser, Subcommand, ValueEnum};
-
-mod error;
-
-type WavGenError = error::WavGenError;
-
-/// Structure used by the `clap` to process the command line arguments
-#[derive(Parser)]
-#[clap(author, version, about, long_about = None)] // Read from `Cargo.toml`
-
-struct Cli {
-    /// Name of the output wave file
-    #[clap(global = true, default_value_t = String::from("sine.wav"), value_parser)]
-    out_file_name: String,
-
-    /// Volume of the generated wave from 0 to 65 535
-    #[clap(global = true, short, long, value_parser, default_value = "1000")]
-    volume: u16,
-
-    #[clap(subcommand)]
-    command: OutputTypeCommands,
-}
-
-#[derive(Subcommand)]
-enum OutputTypeCommands {
-    /// Generate a wav file
-    Wav(WavOptions),
-    /// Generate a rust data structure
-    Rust(RustOptions),
-}
-
-#[derive(Args)]
-struct WavOptions {
-    /// Duration of the generated sine wave in seconds.
-    #[clap(global = true, short, long, value_parser, default_value = "5")]
-    duration: u32,
-
-    #[clap(subcommand)]
-    gen_command: GenCommands,
-}
-
-#[derive(Args)]
-struct RustOptions {
-    /// Length of the generated wave in words. Independent of stereo or mono, only
-    /// this number of entries will be generated. For stereo the length needs to be
-    /// an even number
-    #[clap(global = true, short, long, value_parser, default_value = "1024")]
-    length: u32,
-
-    /// Generate just one cycle of the waveform. Cannot be used with --length
-    #[clap(global = true, short, long, action, conflicts_with("length"))]
-    cycle: bool,
-
-    /// Name of the rust data struct generated
-    #[clap(global = true, short, long, value_parser, default_value = "DATA")]
-    name: String,
-
-    /// Only samples for one channel are generated. This is different
-    /// to stereo (the default) where an entry for the left and then
-    /// for the right channel is generated
-    #[clap(global = true, short, long, action, default_value_t = false)]
-    mono: bool,
-
-    #[clap(subcommand)]
-    gen_command: GenCommands,
-}
-
-/// Structure used by the `clap` to process the subcommands
-#[derive(Subcommand)]
-enum GenCommands {
-    /// Generate a sine wave
-    Sine {
-        /// Frequency of the sine wave in hertz
-        #[clap(short, long, value_parser, default_value = "432")]
-        frequency: u32,
+//!
+//! ## C Header Arrays
+//!
+//! For firmware written in C the same data can be generated as a C header file. The options are the same as
+//! for the rust data arrays:
+//!
+//! ```console
+//! wav-gen c sine --frequency 500 --length 1024 --name SINE_DATA ./sine.h
+//! ```
+//!
+//! The generated header file looks like:
+//!
+//! ```c
+//! #ifndef SINE_DATA_H
+//! #define SINE_DATA_H
+//!
+//! #include <stdint.h>
+//!
+//! #define SINE_DATA_LEN 1024
+//!
+//! static const int16_t SINE_DATA[SINE_DATA_LEN] = {
+//!    // int16_t values
+//! };
+//!
+//! #endif
+//! ```
+//!
+//! The rust only options `--const` and `--split` cannot be used for a C header.
+//!
+//! ## JSON
+//!
+//! For web visualisers and test harnesses the data can be written as a JSON document. The options are the same
+//! as for the rust data arrays:
+//!
+//! ```console
+//! wav-gen json sine --frequency 500 --length 8 ./sine.json
+//! ```
+//!
+//! The JSON document contains the sampling rate, the number of channels, the command line used and the samples:
+//!
+//! ```json
+//! {"channels":2,"command":"wav-gen json sine --frequency 500 --length 8 ./sine.json","sample_rate":44100,"samples":[0,0,71,71,141,141,212,212]}
+//! ```
+//!
+//! ## Raw Samples
+//!
+//! The samples can also be written as raw 16 bit PCM without any header, e.g. to pipe into `aplay` or to
+//! load directly into the memory of a microcontroller. The samples are little-endian unless `--endian big`
+//! is given:
+//!
+//! ```console
+//! wav-gen raw sine --frequency 500 --duration 2 sine.raw
+//! aplay -f S16_LE -c 2 -r 44100 sine.raw
+//! ```
+//!
+//! For telephony, e.g. test vectors for a G.711 codec, the samples can be companded to 8 bits with
+//! `--encoding mulaw` or `--encoding alaw`. Each sample is then one byte. This also works for the rust data
+//! arrays, which are then `[u8; N]`:
+//!
+//! ```console
+//! wav-gen raw sine --frequency 1000 --sampling-rate 8000 --duration 1 --encoding mulaw tone.ulaw
+//! aplay -f MU_LAW -c 2 -r 8000 tone.ulaw
+//! ```
+//!
+//! ## Output Type from the File Name
+//!
+//! The output type subcommand can be left out. The output type is then detected from the extension of the
+//! output file name: `.wav` for `wav`, `.rs` for `rust`, `.h` for `c`, `.json` for `json` or `.raw` for `raw`:
+//!
+//! ```console
+//! wav-gen sine --frequency 440 tone.wav
+//! wav-gen sine --frequency 500 --length 1024 --name SINE_DATA ./sine.h
+//! ```
+//!
+//! Giving the subcommand still overrides the extension of the file name.
+//!
+//! ## Reverse
+//!
+//! With `--reverse` the generated wave is reversed so that it plays backwards, e.g. for a reverse cymbal
+//! effect from a sweep:
+//!
+//! ```console
+//! wav-gen wav sweep --start 2000 --finish 8000 --reverse --fade-out 10 reversed.wav
+//! ```
+//! Fades and envelopes are applied to the reversed wave.
+//!
+//! ## Zero Crossings
+//!
+//! A tone cut to an exact duration usually ends mid-cycle, which causes a click. With `--zero-cross` the end of
+//! the wave is trimmed back to the last frame where all the channels are near zero (within 5% of the peak):
+//!
+//! ```console
+//! wav-gen wav sine --frequency 443 --duration 0.5 --zero-cross tone.wav
+//! ```
+//!
+//! ## Fades
+//!
+//! To avoid clicks at the start and the end of a tone, the wave can be faded in and out linearly with
+//! `--fade-in` and `--fade-out`. The times are in milliseconds:
+//!
+//! ```console
+//! wav-gen wav sine --frequency 440 --fade-in 20 --fade-out 50 sine.wav
+//! ```
+//!
+//! ## Windows
+//!
+//! For spectral analysis the whole wave can be shaped with a `hann` or `hamming` window with `--window`. The
+//! window is applied after all the other processing:
+//!
+//! ```console
+//! wav-gen wav sine --frequency 1000 --duration 0.05 --window hann burst.wav
+//! ```
+//!
+//! ## Lead In and Lead Out
+//!
+//! To align test signals or to give a player time to start, silence can be added before and after the wave with
+//! `--lead-in` and `--lead-out`. The times are in milliseconds and the silence is not part of the `--duration`:
+//!
+//! ```console
+//! wav-gen wav sine --frequency 1000 --duration 1 --lead-in 500 --lead-out 250 padded.wav
+//! ```
+//!
+//! ## Tremolo
+//!
+//! The amplitude of any wave can be modulated by a low frequency sine wave with `--tremolo-rate` in hertz. The
+//! depth of the modulation is from 0.0 to 1.0 and is 0.5 if `--tremolo-depth` is not given. With a depth of 1.0
+//! the amplitude goes all the way down to silence and back up to the full volume:
+//!
+//! ```console
+//! wav-gen wav sine --frequency 440 --tremolo-rate 5 --tremolo-depth 0.3 tremolo.wav
+//! ```
+//!
+//! ## Panning
+//!
+//! Stereo waves have the same samples in the left and right channel. With `--pan` the wave can be panned from
+//! -1.0 (full left) to 1.0 (full right) using a constant power pan law:
+//!
+//! ```console
+//! wav-gen wav sine --frequency 440 --pan -0.5 panned.wav
+//! ```
+//!
+//! ## Limiter
+//!
+//! Waves such as harmonics can have peaks larger than the volume. With `--limiter` the wave is soft clipped with
+//! a `tanh` curve so that the peaks approach the volume gradually instead of being clipped hard:
+//!
+//! ```console
+//! wav-gen wav harmonics --infile harmonics.csv --volume 20000 --limiter harmonics.wav
+//! ```
+//!
+//! ## Normalising
+//!
+//! The peak of waves such as harmonics is hard to predict. With `--normalize` the wave is scaled so that its
+//! peak is the volume:
+//!
+//! ```console
+//! wav-gen wav harmonics --infile harmonics.csv --volume 16000 --normalize harmonics.wav
+//! ```
+//!
+//! ## DC Offset
+//!
+//! For testing DC-coupled inputs a constant offset can be added to every sample with `--dc-offset`. Samples
+//! that would exceed the range of 16 bits are limited to it:
+//!
+//! ```console
+//! wav-gen wav sine --frequency 440 --dc-offset -2000 offset.wav
+//! ```
+//!
+//! ## Clipping
+//!
+//! Samples outside the range of 16 bits, e.g. where harmonics add up to more than the volume or after
+//! `--dc-offset`, are limited to it by default. With `--on-clip wrap` they wrap around to the other end
+//! of the range instead and with `--on-clip error` no file is written:
+//!
+//! ```console
+//! wav-gen wav sine --frequency 440 --dc-offset 4000 --on-clip error offset.wav
+//! ```
+//!
+//! ## ADSR Envelope
+//!
+//! For more musical tones an ADSR (attack, decay, sustain, release) envelope can be applied to any wave. The
+//! attack, decay and release times are in milliseconds and the sustain level is from 0.0 to 1.0:
+//!
+//! ```console
+//! wav-gen wav square --frequency 440 --duration 1 --attack 10 --decay 100 --sustain 0.6 --release 300 note.wav
+//! ```
+//! Stages that are not given are 0 milliseconds long and the sustain level is then 1.0. If the stages are
+//! longer than the wave they are shortened, first the release, then the decay and then the attack.
+//!
+//! For plucked strings, bells and other percussive sounds the wave can decay exponentially with `--decay-tau`. The
+//! wave is multiplied by exp(-t/tau), so that it falls to 37% of its volume after the time constant tau in
+//! milliseconds:
+//!
+//! ```console
+//! wav-gen wav harmonics --infile harmonics.csv --duration 2 --decay-tau 300 pluck.wav
+//! ```
+//!
+//! ## Amplitude Sweep
+//!
+//! To generate a 1000 hertz tone whose level rises from -40dB to 0dB (relative to the volume) over 10 seconds use:
+//!
+//! ```console
+//! wav-gen wav amp-sweep --frequency 1000 --amp-start -40 --amp-end 0 --duration 10 level.wav
+//! ```
+//! The level changes linearly in decibels.
+//!
+//! ## Impulses
+//!
+//! To measure an impulse response, an impulse (a single sample at the start followed by silence) can be generated with:
+//!
+//! ```console
+//! wav-gen wav impulse --volume 32767 --duration 2 impulse.wav
+//! ```
+//! The impulse can be placed at a later sample with `--position`, e.g. `--position 1000` to leave room for the
+//! pre-ringing of a filter, and be made a short click of a few samples with `--width`:
+//!
+//! ```console
+//! wav-gen wav impulse --volume 32767 --position 1000 --width 4 --duration 1 click.wav
+//! ```
+//! A single sample has energy above the Nyquist frequency. With `--band-limited` a windowed sinc is generated in
+//! the centre of the wave instead, with its first zero crossings one sample either side of the centre. The
+//! reported bandwidth allows for the roll off of the Blackman window below the Nyquist frequency.
+//!
+//! ## Click Tracks
+//!
+//! A click track for a metronome has a short click on each beat with silence in between. The tempo is given in
+//! beats per minute and the first beat of each bar, here of 3 beats, is accented with a click an octave higher:
+//!
+//! ```console
+//! wav-gen wav click --bpm 90 --beats 3 --duration 30 click.wav
+//! ```
+//! The length of the clicks (5 milliseconds) and their frequency (2000 hertz) can be changed with `--click-ms`
+//! and `--click-frequency`.
+//!
+//! ## Channel Delays
+//!
+//! For multichannel alignment tests a wave can be copied to a number of channels with each channel delayed
+//! by its own amount. The delays are given in milliseconds with one delay for each channel. The
+//! reference channel (counting from 0, channel 0 if not specified) carries the wave without a delay, so
+//! its delay needs to be 0:
+//!
+//! ```console
+//! wav-gen wav impulse --channel-delays 0,0.5,1,1.5 --reference-channel 0 array.wav
+//! ```
+//!
+//! ## Stepped Tones
+//!
+//! To generate a sequence of steady tones at 100, 200, 500, 1000 and 2000 hertz, each held for
+//! 500 milliseconds with 100 milliseconds of silence between them, use:
+//!
+//! ```console
+//! wav-gen wav stepped-tone --frequencies 100,200,500,1000,2000 --step-ms 500 --gap-ms 100 steps.wav
+//! ```
+//! The length of the generated wave is determined by the steps, so `--duration` is not used.
+//!
+//! ## Sequences
+//!
+//! A sequence of segments created by the different generators can be concatenated into one wave. The segments
+//! are specified in a file with one segment on each line, e.g. `sequence.txt`:
+//!
+//! ```text
+//! # A tone, a pause and then a sweep
+//! sine 440 1s
+//! silence 0.5s
+//! sweep 200 800 2s
+//! noise 250ms
+//! ```
+//! The segments can be `sine`, `square`, `triangle` and `sawtooth` with a frequency, `sweep` with a start and
+//! finishing frequency, or `noise` and `silence`. The duration is given last in seconds (`s`) or milliseconds (`ms`).
+//!
+//! ```console
+//! wav-gen wav sequence --sequence-file sequence.txt sequence.wav
+//! ```
+//! The length of the generated wave is determined by the segments, so `--duration` is not used.
+//!
+//! ## Noise
+//!
+//! To generate 10 seconds of white noise use:
+//!
+//! ```console
+//! wav-gen wav noise --duration 10 noise.wav
+//! ```
+//! The noise can be reproduced by specifying a seed for the random number generator with `--seed`.
+//!
+//! A long noise stream can be split across several files without a seam by saving the state of the
+//! random number generator with `--rng-state-out` and continuing from it in the next run with `--rng-state-in`:
+//!
+//! ```console
+//! wav-gen wav noise --seed 42 --rng-state-out noise.state noise_1.wav
+//! wav-gen wav noise --rng-state-in noise.state --rng-state-out noise.state noise_2.wav
+//! ```
+//! The state file is a text file containing the state as 16 hexadecimal digits.
+//!
+//! ## Silence
+//!
+//! Silence, e.g. as a gap between other waves or as a timing reference, is generated with:
+//!
+//! ```console
+//! wav-gen wav silence --duration 0.5 gap.wav
+//! ```
+//!
+//! ## Multichannel
+//!
+//! A wave with any number of channels, each with its own content, can be generated by describing the channels in
+//! a file (for example `channels.txt`) with one line for each channel:
+//!
+//! ```text
+//! # Channel 0, 1 and 2
+//! sine 440
+//! noise
+//! silence
+//! ```
+//! Each channel is either `sine <frequency>`, `noise` or `silence`. Then use:
+//!
+//! ```console
+//! wav-gen wav multichannel --channels-file channels.txt multichannel.wav
+//! ```
+//! For rust output the `--length` needs to be a multiple of the number of channels.
+//!
+//! Small random changes in level can be added to any wave with `--amplitude-jitter`. Each sample is multiplied by a
+//! random gain of up to plus or minus the given number of decibels. With `--seed` the result is reproducible:
+//!
+//! ```console
+//! wav-gen wav sine --amplitude-jitter 0.5 --seed 7 jittered.wav
+//! ```
+//!
+//! ## Pre-emphasis
+//!
+//! The generated wave can be pre-emphasised with `--preemphasis`, e.g. to test de-emphasis circuits.
+//! The supported standards are:
+//! - `50us` - FM broadcast pre-emphasis with a 50µs time constant (Europe)
+//! - `75us` - FM broadcast pre-emphasis with a 75µs time constant (Americas)
+//! - `riaa` - the RIAA recording curve with the time constants 3180µs, 318µs and 75µs, normalised to 0dB at 1kHz
+//!
+//! The curves are realised as digital filters using the bilinear transform, so the highest frequencies
+//! (above approximately 10kHz at 44100 hertz) are boosted a little more than the standards specify.
+//!
+//! ```console
+//! wav-gen wav sweep --start 20 --finish 20000 --preemphasis 75us sweep_75us.wav
+//! ```
+//!
+//! ## Comb Filter
+//!
+//! A comb filter can be applied to the generated wave with `--comb-delay` (in milliseconds) and `--comb-feedback`:
+//!
+//! ```console
+//! wav-gen wav noise --comb-delay 1 --comb-feedback 0.8 comb.wav
+//! ```
+//! This is a feedback comb filter, `y[n] = (1 - |g|) x[n] + g y[n - delay]`, with notches in the spectrum. The feedback `g`
+//! is limited to between -0.99 and 0.99 so that the filter always decays.
+//!
+//! ## Octave Bands
+//!
+//! The generated wave can be split into octave bands with `--band-split`. Each band is written to its own
+//! file with the centre frequency of the band appended to the file name:
+//!
+//! ```console
+//! wav-gen wav noise --band-split noise.wav
+//! ```
+//! This writes `noise_31hz.wav`, `noise_63hz.wav`, `noise_125hz.wav` up to `noise_16000hz.wav`. The bands are centred
+//! on 1000Hz, with edges a half octave either side of the centre. Only the bands that lie below the Nyquist frequency
+//! are written.
+//!
+//! ## Loudness
+//!
+//! The integrated loudness of the generated wave can be reported in LUFS with `--lufs`:
+//!
+//! ```console
+//! wav-gen wav sine --frequency 1000 --volume 3000 --lufs sine.wav
+//! ```
+//! The loudness is measured as specified in ITU-R BS.1770-4, i.e. with K-weighting and gating of 400ms blocks.
+//! All channels are weighted equally, which is correct for mono and stereo.
+//!
+//! ## Progress
+//!
+//! While long waves are generated a progress bar is shown if stderr is a terminal. It can be switched off
+//! with `--quiet`:
+//!
+//! ```console
+//! wav-gen wav sine --frequency 440 --duration 3600 --max-duration 3600 --quiet long.wav
+//! ```
+//!
+//! When `wav-gen` is built with the `parallel` feature (`cargo install wav-gen --features parallel`) the samples of the
+//! sine, square, pulse, triangle and sawtooth waves, and so also of harmonics, are generated on all the cores. The
+//! number of threads can be set with `--threads`:
+//!
+//! ```console
+//! wav-gen wav harmonics --infile harmonics.csv --duration 3600 --max-duration 3600 --threads 4 long.wav
+//! ```
+//!
+//! Sine, square, pulse (without `--pwm-rate`), triangle and sawtooth (without `--band-limited`) waves are written
+//! to a wav file while they are generated, so that long waves are not held in memory. This is not possible with
+//! the options that work on the whole of the wave, e.g. `--fade-in`, `--normalize` or `--reverse`, with more than
+//! one output file or with RIFX and RF64 files.
+//!
+//! When the file has been written a summary of the wave is shown: the number of samples, the duration, the peak
+//! and the number of samples at full scale, which are most likely clipped:
+//!
+//! ```console
+//! $ wav-gen wav harmonics --infile harmonics.csv --duration 1 harmonics.wav
+//! Finished writing to harmonics.wav
+//! 88200 samples (44100 for each of 2 channels), 1.000s, peak 849 (-31.7dBFS), no clipping
+//! ```
+//!
+//! ## Samples Only
+//!
+//! To pipe the samples into another program use `--samples-only`. This writes the samples to stdout as decimal numbers,
+//! one on each line, without writing the output file:
+//!
+//! ```console
+//! wav-gen rust sine --mono --length 200 --samples-only | gnuplot -p -e "plot '-' with lines"
+//! ```
+//! The samples of the channels are interleaved in the same way as for the rust data arrays.
+//!
+//! ## Preview
+//!
+//! To check the shape of the generated wave without opening another program use `--preview`. This shows an ASCII
+//! preview of the first 10ms of the wave in the terminal:
+//!
+//! ```console
+//! wav-gen wav sine --frequency 300 --preview sine.wav
+//! ```
+//!
+//! ## Checking for Silence
+//!
+//! In scripts, a wave that is silent or nearly silent usually means a mistake in the parameters (such as a volume of 0).
+//! With `--fail-on-silence` the program exits with the code 3, without writing the file, if the peak of the wave is below
+//! a level in dBFS:
+//!
+//! ```console
+//! wav-gen wav harmonics --infile harmonics.csv --fail-on-silence -60 harmonics.wav
+//! ```
+//!
+//! ## Dry Run
+//!
+//! To check the parameters or a harmonics file in a script without writing any files use `--dry-run`. The wave is
+//! generated, so that any errors are reported, and the files that would be written are shown instead:
+//!
+//! ```console
+//! $ wav-gen wav harmonics --infile harmonics.csv --dry-run harmonics.wav
+//! Dry run would write 441000 samples to harmonics.wav (16 bit wav file)
+//! ```
+//!
+//! # Library
+//!
+//! The wave form generators (e.g. `gen_sine_wave`, `gen_sweep_wave` and `gen_harmonics`) are also available as the
+//! library crate `wav_gen` so that they can be used directly from other rust programs.
+//!
+//! # More options
+//! For more options use:
+//!
+//! ```console
+//! wav-gen help
+//! ```
+
+//  Wav format specification: see http://soundfile.sapp.org/doc/WaveFormat/
+
+use num::integer::{gcd, lcm};
+use std::borrow::Cow;
+use std::error::Error;
+use std::f32::consts::PI;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Cursor, IsTerminal, Read, Seek, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use indicatif::{ProgressBar, ProgressStyle};
+use rand::Rng as _;
+use wav::Header;
+use wav_gen::{
+    error, gen_chirp, gen_harmonics, gen_pulse_wave, gen_sawtooth_wave, gen_sine_wave,
+    gen_square_wave, gen_sweep_wave, gen_triangle_wave, report_progress, sawtooth_values,
+    sine_values, square_values, to_float_sample, to_sample, triangle_values, Harmonic, OnClip, Pwm,
+    FLOAT_SCALE,
+};
+
+use clap::{Args, CommandFactory, ErrorKind, Parser, Subcommand, ValueEnum};
+
+mod filter;
+mod g711;
+mod loudness;
+mod preview;
+mod rng;
+mod wav_writer;
+
+use filter::{apply_biquad, apply_comb, octave_bands, Biquad, OctaveBand, BUTTERWORTH_Q};
+use g711::{linear_to_alaw, linear_to_mulaw};
+use loudness::integrated_loudness;
+use preview::print_preview;
+use rng::Rng;
+use wav_writer::{write_rf64, write_rifx, write_samples_le, write_wav_header};
+
+type WavGenError = error::WavGenError;
+
+/// Structure used by the `clap` to process the command line arguments
+#[derive(Parser)]
+#[clap(author, version, about, long_about = None)] // Read from `Cargo.toml`
+struct Cli {
+    /// Name of the output wave file. With - a wav file is written to stdout
+    #[clap(global = true, default_value_t = String::from("sine.wav"), value_parser)]
+    out_file_name: String,
+
+    /// Volume of the generated wave from 0 to 32 767. Larger volumes are reduced to 32 767
+    #[clap(global = true, short, long, value_parser, default_value = "1000")]
+    volume: u16,
+
+    /// Volume of the generated wave in dB relative to the full scale of 16 bit samples,
+    /// e.g. 0 for full scale or -6 for half of it. Used instead of --volume
+    #[clap(
+        global = true,
+        long,
+        value_parser,
+        allow_hyphen_values = true,
+        conflicts_with("volume")
+    )]
+    gain_db: Option<f32>,
+
+    /// Volume of the generated wave as a fraction of the full scale of 16 bit samples from 0.0
+    /// to 1.0, e.g. 0.5 for half of it. Used instead of --volume
+    #[clap(
+        global = true,
+        long,
+        value_parser = parse_amplitude,
+        conflicts_with_all(&["volume", "gain-db"])
+    )]
+    amplitude: Option<f32>,
+
+    /// Multiply each sample by a random gain of up to plus or minus this many dB. Use
+    /// --seed to make the jitter reproducible
+    #[clap(global = true, long, value_parser)]
+    amplitude_jitter: Option<f32>,
+
+    /// Fade in the generated wave linearly over this many milliseconds
+    #[clap(global = true, long, value_parser, default_value = "0")]
+    fade_in: u32,
+
+    /// Fade out the generated wave linearly over this many milliseconds
+    #[clap(global = true, long, value_parser, default_value = "0")]
+    fade_out: u32,
+
+    /// Window applied to the whole of the generated wave
+    #[clap(global = true, long, value_enum, default_value = "none")]
+    window: Window,
+
+    /// Silence in milliseconds before the generated wave
+    #[clap(global = true, long, value_parser, default_value = "0")]
+    lead_in: u32,
+
+    /// Silence in milliseconds after the generated wave
+    #[clap(global = true, long, value_parser, default_value = "0")]
+    lead_out: u32,
+
+    /// Modulate the amplitude of the generated wave with a sine wave of this frequency in hertz
+    #[clap(global = true, long, value_parser)]
+    tremolo_rate: Option<f32>,
+
+    /// Depth of the tremolo from 0.0 to 1.0
+    #[clap(global = true, long, value_parser, default_value = "0.5")]
+    tremolo_depth: f32,
+
+    /// Attack time of an ADSR envelope in milliseconds. The envelope is applied if any of
+    /// --attack, --decay, --sustain or --release is given
+    #[clap(global = true, long, value_parser)]
+    attack: Option<u32>,
+
+    /// Decay time of an ADSR envelope in milliseconds
+    #[clap(global = true, long, value_parser)]
+    decay: Option<u32>,
+
+    /// Sustain level of an ADSR envelope from 0.0 to 1.0
+    #[clap(global = true, long, value_parser)]
+    sustain: Option<f32>,
+
+    /// Release time of an ADSR envelope in milliseconds
+    #[clap(global = true, long, value_parser)]
+    release: Option<u32>,
+
+    /// Time constant of an exponential decay in milliseconds. The generated wave is multiplied by
+    /// exp(-t/tau), e.g. for a plucked string or a bell
+    #[clap(global = true, long, value_parser = parse_time_constant)]
+    decay_tau: Option<f32>,
+
+    /// Pan the stereo wave from -1.0 (full left) to 1.0 (full right) with a constant power pan law
+    #[clap(global = true, long, value_parser, allow_hyphen_values = true)]
+    pan: Option<f32>,
+
+    /// Reverse the generated wave so that it plays backwards
+    #[clap(global = true, long, action)]
+    reverse: bool,
+
+    /// Trim the end of the generated wave back to the last frame where all the channels are
+    /// near zero so that it does not end mid-cycle
+    #[clap(global = true, long, action)]
+    zero_cross: bool,
+
+    /// Scale the generated wave so that its peak is the volume
+    #[clap(global = true, long, action)]
+    normalize: bool,
+
+    /// Soft clip the generated wave with a tanh curve so that the samples approach the volume
+    /// gradually instead of being clipped
+    #[clap(global = true, long, action)]
+    limiter: bool,
+
+    /// Constant offset added to every sample of the generated wave. Samples outside the range
+    /// of 16 bit samples are handled as given by --on-clip
+    #[clap(global = true, long, value_parser, allow_hyphen_values = true)]
+    dc_offset: Option<i16>,
+
+    /// Apply a comb filter with this delay in milliseconds to the generated wave
+    #[clap(global = true, long, value_parser)]
+    comb_delay: Option<f32>,
+
+    /// Feedback of the comb filter, from -0.99 to 0.99
+    #[clap(
+        global = true,
+        long,
+        value_parser,
+        allow_hyphen_values = true,
+        default_value = "0.5"
+    )]
+    comb_feedback: f32,
+
+    /// Maximum duration of the generated wave in seconds. Guards against accidentally
+    /// generating very large files
+    #[clap(global = true, long, value_parser, default_value = "3600")]
+    max_duration: u32,
+
+    /// Apply a pre-emphasis filter to the generated wave
+    #[clap(global = true, long, value_enum)]
+    preemphasis: Option<Preemphasis>,
+
+    /// Seed for the random number generator so that noise can be reproduced. If not
+    /// specified the random number generator is seeded from the system time
+    #[clap(global = true, long, value_parser, conflicts_with("rng-state-in"))]
+    seed: Option<u64>,
+
+    /// File containing the state of the random number generator saved by
+    /// --rng-state-out. Used to continue the noise from a previous run
+    #[clap(global = true, long, value_parser)]
+    rng_state_in: Option<String>,
+
+    /// File to save the state of the random number generator to after the generation
+    #[clap(global = true, long, value_parser)]
+    rng_state_out: Option<String>,
+
+    /// Split the generated wave into octave bands and write each band to its own file. The
+    /// centre frequency of the band is appended to the output file name, e.g. sine_125hz.wav
+    #[clap(global = true, long, action)]
+    band_split: bool,
+
+    /// Fail with the exit code 3 if the peak of the generated wave is below this level in dBFS,
+    /// e.g. -60. No file is written in this case
+    #[clap(global = true, long, value_parser, allow_hyphen_values = true)]
+    fail_on_silence: Option<f32>,
+
+    /// Show an ASCII preview of the start of the generated wave. Only shown when
+    /// writing to a terminal
+    #[clap(global = true, long, action)]
+    preview: bool,
+
+    /// Write the samples to stdout as decimal numbers, one sample on each line, instead
+    /// of writing the output file. The samples of the channels are interleaved
+    #[clap(global = true, long, action, conflicts_with("band-split"))]
+    samples_only: bool,
+
+    /// Generate the wave, so that any errors are reported, but only show the files that would
+    /// be written instead of writing them
+    #[clap(global = true, long, action, conflicts_with_all(&["samples-only", "rng-state-out"]))]
+    dry_run: bool,
+
+    /// Use a lookup table for the sine of the waves generated from harmonics. This is faster
+    /// than calculating each sine but up to about 1e-6 of full scale less accurate
+    #[clap(global = true, long, action)]
+    fast_sine: bool,
+
+    /// Generate only one exact period of a sine, square, triangle or harmonics wave and repeat it
+    /// for the duration. This is faster and the wave is exactly periodic. The frequencies need to be
+    /// whole numbers of hertz
+    #[clap(global = true, long = "loop", action)]
+    loop_period: bool,
+
+    /// What is done with the samples that are outside the range of 16 bit samples, e.g. where
+    /// the harmonics add up to more than the volume or after adding --dc-offset
+    #[clap(global = true, long, value_enum, default_value = "saturate")]
+    on_clip: ClipMode,
+
+    /// Do not show a progress bar while generating long waves
+    #[clap(global = true, short, long, action)]
+    quiet: bool,
+
+    /// Number of threads that generate the samples. Defaults to the number of cores
+    #[cfg(feature = "parallel")]
+    #[clap(global = true, long, value_parser = clap::value_parser!(u32).range(1..))]
+    threads: Option<u32>,
+
+    /// Report the integrated loudness of the generated wave in LUFS (ITU-R BS.1770)
+    #[clap(global = true, long, action)]
+    lufs: bool,
+
+    /// Comma separated list of delays in milliseconds, one for each channel. The wave is generated
+    /// in mono and each channel is a copy of it delayed by its amount. The number of delays
+    /// determines the number of channels
+    #[clap(global = true, long, value_parser, value_delimiter = ',')]
+    channel_delays: Vec<f32>,
+
+    /// The channel (counting from 0) that carries the wave without a delay when using
+    /// --channel-delays. Its delay in the list must be 0
+    #[clap(global = true, long, value_parser, requires("channel-delays"))]
+    reference_channel: Option<usize>,
+
+    #[clap(subcommand)]
+    command: OutputTypeCommands,
+}
+
+#[derive(Subcommand)]
+enum OutputTypeCommands {
+    /// Generate a wav file
+    Wav(WavOptions),
+    /// Generate a rust data structure
+    Rust(RustOptions),
+    /// Generate a C header file with the data as an array
+    C(RustOptions),
+    /// Generate a JSON document with the data and its sampling rate and channels
+    Json(RustOptions),
+    /// Generate raw 16 bit PCM samples without a header
+    Raw(RawOptions),
+    /// Convert an existing wav file to a rust data structure
+    Convert(ConvertOptions),
+}
+
+#[derive(Args)]
+struct ConvertOptions {
+    /// Name of the wav file to convert. Samples that are not 16 bit are converted to 16 bit
+    #[clap(short, long, value_parser)]
+    infile: String,
+
+    /// Name of the rust data struct generated
+    #[clap(short, long, value_parser, default_value = "DATA")]
+    name: String,
+
+    /// Number of samples on each line of the generated data structure
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..), default_value = "10")]
+    columns: u32,
+
+    /// Generate the constants SAMPLE_RATE and CHANNELS without the name instead of
+    /// NAME_SAMPLE_RATE and NAME_CHANNELS
+    #[clap(long, action)]
+    constants: bool,
+
+    /// Generate the data structure as a const instead of a static
+    #[clap(long = "const", action)]
+    const_array: bool,
+}
+
+#[derive(Args)]
+struct WavOptions {
+    /// Duration of the generated wave in seconds, e.g. 2.5
+    #[clap(global = true, short, long, value_parser = parse_duration, default_value = "5")]
+    duration: f32,
+
+    /// Duration of the generated wave in milliseconds, e.g. 20 for a short blip
+    #[clap(global = true, long, value_parser, conflicts_with = "duration")]
+    duration_ms: Option<u32>,
+
+    /// Sampling rate of the generated wave in hertz, e.g. 48000 or 8000
+    #[clap(global = true, long, value_parser = clap::value_parser!(u32).range(1..), default_value = "44100")]
+    sampling_rate: u32,
+
+    /// Bit depth of the samples in the wav file (8, 16 or 24)
+    #[clap(global = true, long, value_enum, default_value = "16")]
+    bit_depth: BitDepth,
+
+    /// Format of the samples in the wav file. Float samples are 32 bit and the wave is scaled by
+    /// volume / 65535
+    #[clap(
+        global = true,
+        long,
+        value_enum,
+        default_value = "int",
+        conflicts_with_all(&["bit-depth", "bit-depths"])
+    )]
+    format: SampleFormat,
+
+    /// Comma separated list of bit depths (8, 16 or 24). A file is written for each
+    /// bit depth with the bit depth appended to the file name, e.g. sine_24bit.wav.
+    /// Without this a single file is written with the bit depth of --bit-depth
+    #[clap(
+        global = true,
+        long,
+        value_enum,
+        value_delimiter = ',',
+        conflicts_with("bit-depth")
+    )]
+    bit_depths: Vec<BitDepth>,
+
+    /// Also write the same data as a rust data structure called DATA to this file
+    #[clap(global = true, long, value_parser, conflicts_with("band-split"))]
+    also_rust: Option<String>,
+
+    /// Write a big-endian RIFX file instead of a little-endian wav (RIFF) file
+    #[clap(global = true, long, action)]
+    rifx: bool,
+
+    /// Add triangular (TPDF) dither of one least significant bit before reducing the samples to
+    /// 8 bits, so that the quantization error is noise instead of distortion. Use --seed to make
+    /// the dither reproducible
+    #[clap(global = true, long, action)]
+    dither: bool,
+
+    /// Write an RF64 file with 64 bit sizes. This is done anyway if the samples are too
+    /// large for a wav file (4GB)
+    #[clap(global = true, long, action, conflicts_with("rifx"))]
+    rf64: bool,
+
+    #[clap(subcommand)]
+    gen_command: GenCommands,
+}
+
+#[derive(Args)]
+struct RustOptions {
+    /// Length of the generated wave in words. Independent of stereo or mono, only
+    /// this number of entries will be generated. The length needs to be a multiple
+    /// of the number of channels
+    #[clap(global = true, short, long, value_parser, default_value = "1024")]
+    length: u32,
+
+    /// Generate just one cycle of the waveform. Cannot be used with --length
+    #[clap(global = true, short, long, action, conflicts_with("length"))]
+    cycle: bool,
+
+    /// Name of the rust data struct generated
+    #[clap(global = true, short, long, value_parser, default_value = "DATA")]
+    name: String,
+
+    /// Number of samples on each line of the generated data structure
+    #[clap(global = true, long, value_parser = clap::value_parser!(u32).range(1..), default_value = "10")]
+    columns: u32,
+
+    /// Only samples for one channel are generated. This is different
+    /// to stereo (the default) where an entry for the left and then
+    /// for the right channel is generated
+    #[clap(global = true, short, long, action, default_value_t = false)]
+    mono: bool,
+
+    /// Number of channels from 1 to 8. Each channel has the same samples
+    #[clap(
+        global = true,
+        long,
+        value_parser = clap::value_parser!(u8).range(1..=8),
+        conflicts_with("mono")
+    )]
+    channels: Option<u8>,
+
+    /// Sampling rate of the generated wave in hertz, e.g. 48000 or 8000
+    #[clap(global = true, long, value_parser = clap::value_parser!(u32).range(1..), default_value = "44100")]
+    sampling_rate: u32,
+
+    /// Generate the constants SAMPLE_RATE and CHANNELS so that the data can be played
+    /// back correctly. For rust data arrays they replace NAME_SAMPLE_RATE and NAME_CHANNELS
+    #[clap(global = true, long, action)]
+    constants: bool,
+
+    /// Generate the rust data structure as a const instead of a static
+    #[clap(global = true, long = "const", action)]
+    const_array: bool,
+
+    /// Generate separate rust arrays NAME_LEFT and NAME_RIGHT for the left and right
+    /// channel instead of interleaving the channels. Can only be used with stereo
+    #[clap(global = true, long, action)]
+    split: bool,
+
+    /// Write the samples as unsigned 16 bit values centred at 32768, e.g. for a DAC that
+    /// expects them at midscale. The rust arrays are then `[u16; N]`
+    #[clap(global = true, long, action)]
+    unsigned: bool,
+
+    /// Scale the samples down to unsigned values of this many bits (1 to 16) for a DAC,
+    /// e.g. 12 for values from 0 to 4095 centred at 2048. Implies --unsigned
+    #[clap(global = true, long, value_parser = clap::value_parser!(u8).range(1..=16))]
+    dac_bits: Option<u8>,
+
+    /// Compand the samples to 8 bits with G.711 μ-law or A-law. The rust arrays are then `[u8; N]`
+    #[clap(
+        global = true,
+        long,
+        value_enum,
+        conflicts_with_all(&["unsigned", "dac-bits"])
+    )]
+    encoding: Option<Encoding>,
+
+    /// Also write the same data as a 16 bit wav file to this file
+    #[clap(global = true, long, value_parser, conflicts_with("band-split"))]
+    also_wav: Option<String>,
+
+    #[clap(subcommand)]
+    gen_command: GenCommands,
+}
+
+#[derive(Args)]
+struct RawOptions {
+    /// Duration of the generated wave in seconds, e.g. 2.5
+    #[clap(global = true, short, long, value_parser = parse_duration, default_value = "5")]
+    duration: f32,
+
+    /// Duration of the generated wave in milliseconds, e.g. 20 for a short blip
+    #[clap(global = true, long, value_parser, conflicts_with = "duration")]
+    duration_ms: Option<u32>,
+
+    /// Sampling rate of the generated wave in hertz, e.g. 48000 or 8000
+    #[clap(global = true, long, value_parser = clap::value_parser!(u32).range(1..), default_value = "44100")]
+    sampling_rate: u32,
+
+    /// Byte order of the samples
+    #[clap(global = true, long, value_enum, default_value = "little")]
+    endian: Endian,
+
+    /// Write the samples as unsigned 16 bit values centred at 32768, e.g. for a DAC that
+    /// expects them at midscale
+    #[clap(global = true, long, action)]
+    unsigned: bool,
+
+    /// Scale the samples down to unsigned values of this many bits (1 to 16) for a DAC,
+    /// e.g. 12 for values from 0 to 4095 centred at 2048. Implies --unsigned. The values
+    /// are still written as 16 bits
+    #[clap(global = true, long, value_parser = clap::value_parser!(u8).range(1..=16))]
+    dac_bits: Option<u8>,
+
+    /// Compand the samples to 8 bits with G.711 μ-law or A-law. Each sample is then one byte
+    #[clap(
+        global = true,
+        long,
+        value_enum,
+        conflicts_with_all(&["unsigned", "dac-bits"])
+    )]
+    encoding: Option<Encoding>,
+
+    #[clap(subcommand)]
+    gen_command: GenCommands,
+}
+
+/// Structure used by the `clap` to process the subcommands
+#[derive(Subcommand)]
+enum GenCommands {
+    /// Generate a sine wave
+    Sine {
+        /// Frequency of the sine wave in hertz
+        #[clap(short, long, value_parser = clap::value_parser!(u32).range(1..), default_value = "432")]
+        frequency: u32,
+
+        /// Phase of the sine wave at the start in degrees, e.g. 90 for a cosine
+        #[clap(
+            short,
+            long,
+            value_parser,
+            allow_hyphen_values = true,
+            default_value = "0"
+        )]
+        phase: f32,
+
+        /// Frequency of the right channel in hertz. The left channel then has the frequency
+        /// given by --frequency
+        #[clap(long, value_parser = clap::value_parser!(u32).range(1..))]
+        frequency_right: Option<u32>,
+
+        /// Note name instead of the frequency, e.g. A4, C#5 or Bb3. The frequency is in equal
+        /// temperament with A4 as 440 hertz
+        #[clap(long, value_parser = note_to_frequency, conflicts_with("frequency"))]
+        note: Option<f32>,
+
+        /// Modulate the frequency with a sine wave of this frequency in hertz
+        #[clap(
+            long,
+            value_parser,
+            conflicts_with_all(&["phase", "frequency-right"])
+        )]
+        vibrato_rate: Option<f32>,
+
+        /// Depth of the vibrato in cents, i.e. the largest deviation from the frequency
+        #[clap(long, value_parser, default_value = "50")]
+        vibrato_depth: f32,
+    },
+
+    /// Generate a square wave
+    Square {
+        /// Frequency of the square wave in hertz
+        #[clap(short, long, value_parser = clap::value_parser!(u32).range(1..), default_value = "432")]
+        frequency: u32,
+
+        /// Fraction of each cycle, from 0.0 to 1.0, for which the wave is high
+        #[clap(long, value_parser, default_value = "0.5")]
+        duty_cycle: f32,
+    },
+
+    /// Generate a pulse wave whose duty cycle can be swept by a low frequency sine wave (pulse
+    /// width modulation). Without --pwm-rate this is a square wave
+    Pulse {
+        /// Frequency of the pulse wave in hertz
+        #[clap(short, long, value_parser = clap::value_parser!(u32).range(1..), default_value = "432")]
+        frequency: u32,
+
+        /// Fraction of each cycle, from 0.0 to 1.0, for which the wave is high
+        #[clap(long, value_parser, default_value = "0.5")]
+        duty: f32,
+
+        /// Frequency in hertz of the sine wave that modulates the duty cycle
+        #[clap(long, value_parser)]
+        pwm_rate: Option<f32>,
+
+        /// Largest change of the duty cycle by the modulation, e.g. 0.25 to sweep a duty of 0.5
+        /// between 0.25 and 0.75
+        #[clap(long, value_parser, default_value = "0.25", requires = "pwm-rate")]
+        pwm_depth: f32,
+    },
+
+    /// Generate a triangle wave
+    Triangle {
+        /// Frequency of the triangle wave in hertz
+        #[clap(short, long, value_parser = clap::value_parser!(u32).range(1..), default_value = "432")]
+        frequency: u32,
+    },
+
+    /// Generate a sawtooth wave
+    Sawtooth {
+        /// Frequency of the sawtooth wave in hertz
+        #[clap(short, long, value_parser = clap::value_parser!(u32).range(1..), default_value = "432")]
+        frequency: u32,
+
+        /// Ramp down from +volume to -volume in each cycle instead of up
+        #[clap(long, action)]
+        descending: bool,
+
+        /// Sum the harmonics below the Nyquist frequency instead of generating the ramp so that
+        /// the wave does not alias
+        #[clap(long, action)]
+        band_limited: bool,
+    },
+
+    /// Generate a sine wave that sweeps from one frequency to another over the duration
+    Sweep {
+        /// The starting frequency in hertz
+        #[clap(short, long, value_parser, default_value = "100")]
+        start: u32,
+
+        /// The finishing frequency in hertz
+        #[clap(short, long, value_parser, default_value = "2000")]
+        finish: u32,
+
+        /// Sweep from the start to the finishing frequency over the first half of the
+        /// duration and then back to the start frequency
+        #[clap(short = 'r', long, action)]
+        sweep_return: bool,
+
+        /// Time in milliseconds the start frequency is held before the sweep begins. This
+        /// is added to the duration
+        #[clap(long, value_parser, default_value = "0")]
+        pre_hold_ms: u32,
+
+        /// Time in milliseconds the last frequency is held after the sweep ends. This
+        /// is added to the duration
+        #[clap(long, value_parser, default_value = "0")]
+        post_hold_ms: u32,
+
+        /// Sweep the frequency exponentially so that each octave takes the same time. The
+        /// frequencies must be greater than 0
+        #[clap(long, action)]
+        log: bool,
+    },
+
+    /// Generate a sine wave whose level ramps from one level to another over the duration
+    AmpSweep {
+        /// Frequency of the sine wave in hertz
+        #[clap(short, long, value_parser, default_value = "1000")]
+        frequency: u32,
+
+        /// Level at the start in dB relative to the volume
+        #[clap(
+            short = 's',
+            long,
+            value_parser,
+            allow_hyphen_values = true,
+            default_value = "-40"
+        )]
+        amp_start: f32,
+
+        /// Level at the end in dB relative to the volume
+        #[clap(
+            short = 'e',
+            long,
+            value_parser,
+            allow_hyphen_values = true,
+            default_value = "0"
+        )]
+        amp_end: f32,
+    },
+
+    /// Generate a wave that combines the sine waves specified in a external csv file.
+    Harmonics {
+        /// Name of the csv file containing the harmonics. With - the harmonics are read from stdin
+        #[clap(short, long, default_value_t = String::from("harmonics.csv"),value_parser)]
+        infile: String,
+
+        /// Use the amplitudes from the csv file as they are instead of normalising them. It is
+        /// then up to you to avoid clipping, e.g. with --limiter
+        #[clap(long, action)]
+        no_normalize: bool,
+
+        /// The amplitudes in the csv file are in dB relative to full scale (e.g. -6) instead of ratios
+        #[clap(long, action)]
+        amplitude_db: bool,
+    },
+
+    /// Generate a chord from the sine waves of notes with equal amplitudes
+    Chord {
+        /// Comma separated list of note names, e.g. C4,E4,G4
+        #[clap(short, long, value_parser = note_to_frequency, value_delimiter = ',', required = true)]
+        notes: Vec<f32>,
+    },
+
+    /// Generate the DTMF (touch tone) signal of a telephone key from its row and column tones
+    Dtmf {
+        /// The key, one of 0-9, *, #, A, B, C or D
+        #[clap(long, value_parser = parse_dtmf_digit)]
+        digit: char,
+    },
+
+    /// Generate binaural beats, a sine wave of the carrier frequency in the left channel and of the
+    /// carrier plus the beat frequency in the right channel. Can only be used with stereo
+    Binaural {
+        /// Frequency of the left channel in hertz
+        #[clap(long, value_parser)]
+        carrier: f32,
+
+        /// Difference in hertz between the frequencies of the right and the left channel
+        #[clap(long, value_parser, allow_hyphen_values = true)]
+        beat: f32,
+    },
+
+    /// Generate a tone from the partials of a fundamental with amplitudes falling as 1/n (see
+    /// --rolloff). With an inharmonicity coefficient the partials are stretched as for a piano string
+    Partials {
+        /// Frequency of the fundamental in hertz
+        #[clap(short, long, value_parser, default_value = "220")]
+        fundamental: f32,
+
+        /// Number of partials including the fundamental
+        #[clap(short, long, value_parser, default_value = "8")]
+        partials: u32,
+
+        /// Inharmonicity coefficient B. The frequency of the nth partial is f0 * n * sqrt(1 + B * n^2)
+        #[clap(short = 'b', long, value_parser, default_value = "0")]
+        inharmonicity: f32,
+
+        /// How the amplitudes of the partials fall with the partial number n
+        #[clap(long, value_enum, default_value = "1/n")]
+        rolloff: Rolloff,
+    },
+
+    /// Generate a wavetable of single cycle frames that morph from one spectrum to another. The
+    /// length of the generated wave is determined by the frames.
+    Wavetable {
+        /// Number of frames
+        #[clap(short, long, value_parser = clap::value_parser!(u32).range(1..), default_value = "64")]
+        frames: u32,
+
+        /// Number of samples in each frame. Must be a power of two from 4 to 65536
+        #[clap(short = 's', long, value_parser = parse_frame_size, default_value = "2048")]
+        frame_size: u32,
+
+        /// Spectrum of the first frame
+        #[clap(long, value_enum, default_value = "sine")]
+        from: Spectrum,
+
+        /// Spectrum of the last frame
+        #[clap(long, value_enum, default_value = "saw")]
+        to: Spectrum,
+    },
+
+    /// Generate an impulse, a single sample at the start followed by silence
+    Impulse {
+        /// Generate a band limited impulse instead, a windowed sinc in the centre of the wave
+        /// with its first zero crossings one sample either side (i.e. cut off at the Nyquist frequency)
+        #[clap(short = 'b', long, action)]
+        band_limited: bool,
+
+        /// Index of the sample (counting from 0) where the impulse starts
+        #[clap(
+            long,
+            value_parser,
+            default_value = "0",
+            conflicts_with("band-limited")
+        )]
+        position: u32,
+
+        /// Number of samples of the impulse, e.g. 4 for a short click
+        #[clap(long, value_parser = clap::value_parser!(u32).range(1..), default_value = "1", conflicts_with("band-limited"))]
+        width: u32,
     },
 
-    /// Generate a sine wave that sweeps from one frequency to another over the duration
-    Sweep {
-        /// The starting frequency in hertz
-        #[clap(short, long, value_parser, default_value = "100")]
-        start: u32,
+    /// Generate a click track for a metronome, a short click on each beat with silence in between.
+    /// The first beat of each bar is accented with a click an octave higher
+    Click {
+        /// Tempo in beats per minute
+        #[clap(long, value_parser = parse_bpm, default_value = "120")]
+        bpm: f32,
+
+        /// Number of beats in each bar
+        #[clap(long, value_parser = clap::value_parser!(u32).range(1..), default_value = "4")]
+        beats: u32,
+
+        /// Length of each click in milliseconds
+        #[clap(long, value_parser = clap::value_parser!(u32).range(1..), default_value = "5")]
+        click_ms: u32,
+
+        /// Frequency of the tone of the clicks in hertz. The accented clicks are twice this
+        #[clap(long, value_parser, default_value = "2000")]
+        click_frequency: u32,
+    },
+
+    /// Generate white noise
+    Noise,
+
+    /// Generate silence, i.e. samples that are all zero
+    Silence,
+
+    /// Generate a wave with any number of channels, each with its own content as specified
+    /// in a file. The file has one line for each channel which is either `sine <frequency>`,
+    /// `noise` or `silence`.
+    Multichannel {
+        /// Name of the file specifying the content of each channel
+        #[clap(short = 'f', long, value_parser, default_value = "channels.txt")]
+        channels_file: String,
+    },
+
+    /// Generate a sequence of segments that are specified in a file. The file has one line for each
+    /// segment which is either `sine <frequency> <duration>`, `square <frequency> <duration>`,
+    /// `triangle <frequency> <duration>`, `sawtooth <frequency> <duration>`,
+    /// `sweep <start> <finish> <duration>`, `noise <duration>` or `silence <duration>`. The durations
+    /// are in seconds (e.g. 1.5s) or milliseconds (e.g. 250ms). The length of the generated wave
+    /// is determined by the segments.
+    Sequence {
+        /// Name of the file specifying the segments
+        #[clap(short = 'f', long, value_parser, default_value = "sequence.txt")]
+        sequence_file: String,
+    },
+
+    /// Generate a sequence of steady sine tones, each held for a fixed time. The length of the
+    /// generated wave is determined by the steps.
+    SteppedTone {
+        /// Comma separated list of the frequencies in hertz, e.g. 100,200,500,1000
+        #[clap(short, long, value_parser, value_delimiter = ',', required = true)]
+        frequencies: Vec<u32>,
+
+        /// Time each tone is held in milliseconds
+        #[clap(short, long, value_parser, default_value = "1000")]
+        step_ms: u32,
+
+        /// Silence between the tones in milliseconds
+        #[clap(short, long, value_parser, default_value = "0")]
+        gap_ms: u32,
+    },
+}
+
+/// The types of output, named as their subcommands
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+enum OutputType {
+    Wav,
+    Rust,
+    C,
+    Json,
+    Raw,
+}
+
+impl OutputType {
+    /// The name of the subcommand for the output type
+    fn subcommand(&self) -> &'static str {
+        match self {
+            OutputType::Wav => "wav",
+            OutputType::Rust => "rust",
+            OutputType::C => "c",
+            OutputType::Json => "json",
+            OutputType::Raw => "raw",
+        }
+    }
+}
+
+/// What is done with a sample that is outside the range of `i16`, given on the command line for `OnClip`
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum ClipMode {
+    /// Limit the sample to the largest or smallest 16 bit sample
+    Saturate,
+    /// Wrap the sample around to the other end of the range
+    Wrap,
+    /// Fail with an error
+    Error,
+}
+
+impl From<ClipMode> for OnClip {
+    fn from(clip_mode: ClipMode) -> Self {
+        match clip_mode {
+            ClipMode::Saturate => OnClip::Saturate,
+            ClipMode::Wrap => OnClip::Wrap,
+            ClipMode::Error => OnClip::Error,
+        }
+    }
+}
+
+/// The pre-emphasis standards that are supported
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Preemphasis {
+    /// 50µs time constant (FM broadcast in Europe)
+    #[clap(name = "50us")]
+    Us50,
+    /// 75µs time constant (FM broadcast in the Americas)
+    #[clap(name = "75us")]
+    Us75,
+    /// RIAA recording curve with the time constants 3180µs, 318µs and 75µs
+    Riaa,
+}
+
+/// The windows that can be applied to the generated wave
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Window {
+    /// Raised cosine that starts and ends at 0
+    Hann,
+    /// Raised cosine that starts and ends at 0.08
+    Hamming,
+    /// No window
+    None,
+}
+
+/// The number of bits in each sample of a wav file
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum BitDepth {
+    #[clap(name = "8")]
+    Eight,
+    #[clap(name = "16")]
+    Sixteen,
+    #[clap(name = "24")]
+    TwentyFour,
+    /// 32 bit floating point samples, selected with --format float
+    #[clap(skip)]
+    Float,
+}
+
+impl BitDepth {
+    fn bits(&self) -> u16 {
+        match self {
+            BitDepth::Eight => 8,
+            BitDepth::Sixteen => 16,
+            BitDepth::TwentyFour => 24,
+            BitDepth::Float => 32,
+        }
+    }
+
+    /// The value of the format field in the wav header
+    fn audio_format(&self) -> u16 {
+        match self {
+            BitDepth::Float => wav::header::WAV_FORMAT_IEEE_FLOAT,
+            _ => wav::header::WAV_FORMAT_PCM,
+        }
+    }
+}
+
+/// The byte order of raw samples
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Endian {
+    Big,
+    Little,
+}
+
+/// The G.711 encodings that compand the samples to 8 bits
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Encoding {
+    /// μ-law as used in North America and Japan
+    Mulaw,
+    /// A-law as used in Europe
+    Alaw,
+}
+
+/// The formats of the samples in a wav file
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum SampleFormat {
+    /// Integer samples with the bit depth given by --bit-depth
+    Int,
+    /// 32 bit floating point samples from -1.0 to 1.0
+    Float,
+}
+
+/// The spectra of the wavetable frames
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Spectrum {
+    /// Only the fundamental
+    Sine,
+    /// All harmonics with amplitude 1/n
+    Saw,
+    /// Odd harmonics with amplitude 1/n
+    Square,
+}
+
+impl Spectrum {
+    /// The amplitude of the nth harmonic
+    fn amplitude(&self, n: u32) -> f32 {
+        match self {
+            Spectrum::Sine if n == 1 => 1.,
+            Spectrum::Sine => 0.,
+            Spectrum::Saw => 1. / n as f32,
+            Spectrum::Square if n % 2 == 1 => 1. / n as f32,
+            Spectrum::Square => 0.,
+        }
+    }
+}
+
+/// How the amplitudes of the partials fall
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Rolloff {
+    /// Amplitude 1/n, the spectrum of a sawtooth wave
+    #[clap(name = "1/n")]
+    Inverse,
+    /// Amplitude 1/n^2, a softer tone like that of a triangle wave
+    #[clap(name = "1/n^2")]
+    InverseSquare,
+    /// All partials with the same amplitude
+    Flat,
+}
+
+impl Rolloff {
+    /// The amplitude of the nth partial
+    fn amplitude(&self, n: f32) -> f32 {
+        match self {
+            Rolloff::Inverse => 1. / n,
+            Rolloff::InverseSquare => 1. / (n * n),
+            Rolloff::Flat => 1.,
+        }
+    }
+}
+
+enum GeneratedSize {
+    NumberSamples(u32),
+    Cyclic,
+}
+
+/// The frequency of a sweep over time, in number of samples. The sweep can be held
+/// at the frequencies before and after it and can return to the start frequency.
+struct SweepProfile {
+    start: f32,  // Start frequency in hertz
+    finish: f32, // Finishing frequency in hertz
+    pre_hold: u32,
+    sweep: u32,
+    post_hold: u32,
+    sweep_return: bool,
+    log: bool, // Exponential instead of linear sweep
+}
+
+impl SweepProfile {
+    /// The total number of samples of the holds and the sweep
+    fn number_samples(&self) -> u32 {
+        self.pre_hold + self.sweep + self.post_hold
+    }
+
+    /// The frequency in hertz at sample `t`
+    fn frequency_at(&self, t: u32) -> f32 {
+        if t < self.pre_hold {
+            return self.start;
+        }
+
+        let t = t - self.pre_hold;
+        if t >= self.sweep {
+            return if self.sweep_return {
+                self.start
+            } else {
+                self.finish
+            };
+        }
+
+        let t = t as f32;
+        if self.sweep_return {
+            // Up to the finishing frequency over the first half, then back again
+            let half = (self.sweep / 2).max(1) as f32;
+            if t < half {
+                self.interpolate(t / half)
+            } else {
+                self.interpolate(1. - (t - half) / (self.sweep as f32 - half))
+            }
+        } else {
+            self.interpolate(t / self.sweep as f32)
+        }
+    }
+
+    /// The frequency at the fraction `x` of the way from the start frequency (0.0) to the
+    /// finishing frequency (1.0)
+    fn interpolate(&self, x: f32) -> f32 {
+        if self.log {
+            self.start * (self.finish / self.start).powf(x)
+        } else {
+            self.start + (self.finish - self.start) * x
+        }
+    }
+}
+
+/// A segment of a sequence with its duration in seconds
+enum Segment {
+    Sine(u32, f32), // Frequency in hertz
+    Square(u32, f32),
+    Triangle(u32, f32),
+    Sawtooth(u32, f32),
+    Sweep(u32, u32, f32), // Start and finishing frequency in hertz
+    Noise(f32),
+    Silence(f32),
+}
+
+/// The content of one channel of a multichannel wave
+enum ChannelSpec {
+    Sine(u32), // Frequency in hertz
+    Noise,
+    Silence,
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("Error: {}", error);
+        // Report the underlying causes, e.g. the reason a file could not be read
+        let mut source = error.source();
+        while let Some(cause) = source {
+            eprintln!("Caused by: {}", cause);
+            source = cause.source();
+        }
+        process::exit(error.exit_code());
+    }
+}
+
+/// Parses the command line arguments. If the wave form is given without an output type
+/// subcommand, e.g. `wav-gen sine tone.rs`, the output type is detected from the extension
+/// of the output file name and parsed as if its subcommand had been given.
+fn parse_cli() -> Cli {
+    parse_cli_from(std::env::args_os().collect()).unwrap_or_else(|e| e.exit())
+}
+
+/// Parses the command line arguments `args` as described for `parse_cli`
+fn parse_cli_from(args: Vec<OsString>) -> Result<Cli, clap::Error> {
+    // Without an output type the wave form is taken as the output file name, which leaves the
+    // subcommand missing or the arguments after it unknown. Any other error is reported as it is.
+    let error = match Cli::try_parse_from(&args) {
+        Ok(cli) => return Ok(cli),
+        Err(e) => e,
+    };
+    if !matches!(
+        error.kind(),
+        ErrorKind::MissingSubcommand
+            | ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+            | ErrorKind::UnknownArgument
+            | ErrorKind::DisplayHelp
+    ) {
+        return Err(error);
+    }
+
+    // An argument with the name of a wave form can also be the value of an option, so the output
+    // type is tried in front of each of them until the arguments parse. Options only valid for some
+    // output types fail the parse for the others, so each output type is tried until one agrees with
+    // the output file name it parses.
+    let gen_indices =
+        (1..args.len()).filter(|i| args[*i].to_str().is_some_and(GenCommands::has_subcommand));
+    let mut detected = None;
+    let mut undetected = None;
+    let mut errors = Vec::new();
+    for gen_index in gen_indices {
+        for output_type in OutputType::value_variants() {
+            let mut output_args = args.clone();
+            output_args.insert(gen_index, output_type.subcommand().into());
+
+            match Cli::try_parse_from(output_args) {
+                Ok(cli) => match detect_output_type(&cli.out_file_name) {
+                    Some(file_type) if file_type == *output_type => return Ok(cli),
+                    Some(file_type) => detected = Some(file_type),
+                    None => undetected = Some(cli.out_file_name),
+                },
+                // Help is the same for all output types so show it at once
+                Err(e) if !e.use_stderr() => return Err(e),
+                Err(e) => errors.push((*output_type, e)),
+            }
+        }
+    }
+
+    if detected.is_none() {
+        if let Some(out_file_name) = undetected {
+            return Err(Cli::command().error(
+                ErrorKind::ValueValidation,
+                format!(
+                    "cannot detect the output type from the extension of {:?}, use \
+                     .wav, .rs, .h, .json or .raw or give the output type as a \
+                     subcommand, e.g. wav-gen wav sine",
+                    out_file_name
+                ),
+            ));
+        }
+    }
+
+    // Report why the output type of the file name could not be parsed
+    match errors
+        .iter()
+        .position(|(output_type, _)| Some(*output_type) == detected)
+    {
+        Some(i) => Err(errors.swap_remove(i).1),
+        None if errors.is_empty() || error.kind() == ErrorKind::DisplayHelp => Err(error),
+        None => Err(errors.swap_remove(0).1),
+    }
+}
+
+/// The output type for the extension of `file_name`: `.wav`, `.rs`, `.h`, `.json` or `.raw`.
+/// Writing to stdout with `-` is a wav file.
+fn detect_output_type(file_name: &str) -> Option<OutputType> {
+    if file_name == "-" {
+        return Some(OutputType::Wav);
+    }
+
+    let extension = Path::new(file_name).extension()?.to_str()?;
+    match extension.to_ascii_lowercase().as_str() {
+        "wav" => Some(OutputType::Wav),
+        "rs" => Some(OutputType::Rust),
+        "h" => Some(OutputType::C),
+        "json" => Some(OutputType::Json),
+        "raw" => Some(OutputType::Raw),
+        _ => None,
+    }
+}
+
+/// Generate wav files from the command line arguments provided.
+fn run() -> Result<(), WavGenError> {
+    let cli = parse_cli();
+
+    #[cfg(feature = "parallel")]
+    if let Some(threads) = cli.threads {
+        // Only fails if the thread pool has already been set up, which it has not
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads as usize)
+            .build_global()
+            .expect("the thread pool is only built once");
+    }
+
+    let volume = match (cli.gain_db, cli.amplitude) {
+        (Some(gain_db), _) => (10f32.powf(gain_db / 20.) * i16::MAX as f32)
+            .round()
+            .min(u16::MAX as f32) as u16,
+        (None, Some(amplitude)) => (amplitude * i16::MAX as f32).round() as u16,
+        (None, None) => cli.volume,
+    };
+    // The samples are i16 so a larger volume would clip the wave
+    let volume = if volume > i16::MAX as u16 {
+        eprintln!(
+            "Warning: the volume of {} is larger than the largest sample value and is reduced to {}",
+            volume,
+            i16::MAX
+        );
+        i16::MAX as u16
+    } else {
+        volume
+    };
+
+    let (gen_command, sampling_rate) = match cli.command {
+        OutputTypeCommands::Wav(ref wav_options) => {
+            (&wav_options.gen_command, wav_options.sampling_rate)
+        }
+        OutputTypeCommands::Raw(ref raw_options) => {
+            (&raw_options.gen_command, raw_options.sampling_rate)
+        }
+        OutputTypeCommands::Rust(ref rust_options)
+        | OutputTypeCommands::C(ref rust_options)
+        | OutputTypeCommands::Json(ref rust_options) => {
+            (&rust_options.gen_command, rust_options.sampling_rate)
+        }
+        // Nothing is generated, the samples come from the wav file
+        OutputTypeCommands::Convert(ref convert_options) => {
+            return convert_wav(convert_options, Path::new(&cli.out_file_name), cli.dry_run);
+        }
+    };
+
+    // For multichannel waves the number of channels is given by the channels file
+    let channel_specs = match gen_command {
+        GenCommands::Multichannel { channels_file } => {
+            Some(read_channel_specs(Path::new(channels_file))?)
+        }
+        _ => None,
+    };
+
+    if channel_specs.is_some() && !cli.channel_delays.is_empty() {
+        let mut cmd = Cli::command();
+        cmd.error(
+            ErrorKind::ArgumentConflict,
+            "--channel-delays cannot be used with multichannel",
+        )
+        .exit();
+    }
+
+    let channel_delays = match cli.channel_delays.as_slice() {
+        [] => None,
+        delays => {
+            let reference_channel = cli.reference_channel.unwrap_or_default();
+            match delays.get(reference_channel) {
+                None => {
+                    return Err(WavGenError::ReferenceChannelOutOfRange(
+                        reference_channel,
+                        delays.len(),
+                    ))
+                }
+                Some(delay) if *delay != 0. => {
+                    return Err(WavGenError::ReferenceChannelDelayed(reference_channel))
+                }
+                _ => (),
+            }
+            if delays.iter().any(|delay| *delay < 0.) {
+                let mut cmd = Cli::command();
+                cmd.error(
+                    ErrorKind::InvalidValue,
+                    "The channel delays cannot be negative",
+                )
+                .exit();
+            }
+            Some(delays)
+        }
+    };
+
+    // The number of channels given by the channels file or the channel delays has to fit in the wav header
+    let listed_channels = match (&channel_specs, channel_delays) {
+        (Some(specs), _) => Some(specs.len()),
+        (None, Some(delays)) => Some(delays.len()),
+        (None, None) => None,
+    }
+    .map(|n_channels| {
+        u8::try_from(n_channels).map_err(|_| WavGenError::TooManyChannels(n_channels))
+    })
+    .transpose()?;
+
+    // Process output type command options
+    let (size, number_channels) = match cli.command {
+        OutputTypeCommands::Wav(WavOptions {
+            duration,
+            duration_ms,
+            ..
+        })
+        | OutputTypeCommands::Raw(RawOptions {
+            duration,
+            duration_ms,
+            ..
+        }) => {
+            let n_channels = listed_channels.unwrap_or(2);
+            let n_samples = match duration_ms {
+                // Rounded to the nearest sample
+                Some(duration_ms) => {
+                    ((duration_ms as u64 * sampling_rate as u64 + 500) / 1000) as f64
+                }
+                // Checked before calculating the number of samples as this could overflow
+                None if duration > cli.max_duration as f32 => {
+                    return Err(WavGenError::MaxDurationExceeded(cli.max_duration));
+                }
+                None => (duration as f64 * sampling_rate as f64).round(),
+            };
+            if n_samples > u32::MAX as f64 {
+                return Err(WavGenError::MaxDurationExceeded(cli.max_duration));
+            }
+            (GeneratedSize::NumberSamples(n_samples as u32), n_channels)
+        }
+        OutputTypeCommands::Rust(ref rust_options)
+        | OutputTypeCommands::C(ref rust_options)
+        | OutputTypeCommands::Json(ref rust_options) => {
+            if matches!(cli.command, OutputTypeCommands::C(_))
+                && (rust_options.const_array || rust_options.split)
+            {
+                let mut cmd = Cli::command();
+                cmd.error(
+                    ErrorKind::ArgumentConflict,
+                    "--const and --split can only be used for rust data arrays",
+                )
+                .exit();
+            }
+
+            if rust_options.channels.is_some()
+                && (channel_specs.is_some() || channel_delays.is_some())
+            {
+                let mut cmd = Cli::command();
+                cmd.error(
+                    ErrorKind::ArgumentConflict,
+                    "--channels cannot be used with multichannel or --channel-delays",
+                )
+                .exit();
+            }
+
+            let n_channels: u8 = match listed_channels {
+                Some(n_channels) => n_channels,
+                None => match rust_options.channels {
+                    Some(channels) => channels,
+                    None if rust_options.mono => 1,
+                    None => 2,
+                },
+            };
+
+            if rust_options.split && n_channels != 2 {
+                let mut cmd = Cli::command();
+                cmd.error(
+                    ErrorKind::ArgumentConflict,
+                    "--split can only be used with stereo",
+                )
+                .exit();
+            }
+
+            // The length needs to be a multiple of the number of channels so that a sample
+            // is present for each channel
+            if rust_options.length % n_channels as u32 != 0 {
+                let message = if n_channels == 2 {
+                    String::from(
+                        "With stereo the length of the data structure needs to be an even number",
+                    )
+                } else {
+                    format!(
+                        "With {} channels the length of the data structure needs to be a multiple of {}",
+                        n_channels, n_channels
+                    )
+                };
+                let mut cmd = Cli::command();
+                cmd.error(ErrorKind::InvalidValue, message).exit();
+            }
+            let size = if !rust_options.cycle {
+                GeneratedSize::NumberSamples(rust_options.length / n_channels as u32)
+            } else {
+                GeneratedSize::Cyclic
+            };
+
+            (size, n_channels)
+        }
+        OutputTypeCommands::Convert(_) => unreachable!("converted before generating"),
+    };
+
+    if let GeneratedSize::NumberSamples(n_samples) = size {
+        check_duration(n_samples, sampling_rate, cli.max_duration)?;
+    }
+
+    let mut rng = match (&cli.rng_state_in, cli.seed) {
+        (Some(state_file), _) => Rng::read_state(Path::new(state_file))?,
+        (None, Some(seed)) => Rng::from_seed(seed),
+        (None, None) => Rng::from_time(),
+    };
+
+    // With channel delays a mono wave is generated that is then copied to the channels
+    let output_channels = number_channels;
+    let number_channels = if channel_delays.is_some() {
+        1
+    } else {
+        number_channels
+    };
+
+    let progress_bar = match size {
+        GeneratedSize::NumberSamples(n_samples) if !cli.quiet => new_progress_bar(n_samples),
+        _ => ProgressBar::hidden(),
+    };
+    let mut progress = |samples_done: u32| progress_bar.set_position(samples_done as u64);
+
+    let loopable = matches!(
+        gen_command,
+        GenCommands::Sine {
+            frequency_right: None,
+            vibrato_rate: None,
+            ..
+        } | GenCommands::Square { .. }
+            | GenCommands::Triangle { .. }
+            | GenCommands::Harmonics { .. }
+    );
+    if cli.loop_period && !loopable {
+        let mut cmd = Cli::command();
+        cmd.error(
+            ErrorKind::ArgumentConflict,
+            "--loop can only be used with sine (without --frequency-right or --vibrato-rate), square, triangle or harmonics",
+        )
+        .exit();
+    }
+
+    // The simple waves are written while they are generated so that long waves are not held in memory
+    let streamed = match (&size, stream_bit_depth(&cli)) {
+        (GeneratedSize::NumberSamples(n_samples), Some(bit_depth))
+            if fits_wav_file(*n_samples, number_channels, bit_depth) =>
+        {
+            streamed_values(gen_command, sampling_rate)?
+                .map(|values| (values, *n_samples, bit_depth))
+        }
+        _ => None,
+    };
+    if let Some((values, n_samples, bit_depth)) = streamed {
+        let out_path = Path::new(&cli.out_file_name);
+        let stats = write_wav_stream(
+            values,
+            volume,
+            n_samples,
+            number_channels,
+            bit_depth,
+            sampling_rate,
+            out_path,
+            &mut progress,
+        )?;
+        progress_bar.finish_and_clear();
+        print_finished(&[out_path.to_path_buf()]);
+        print_summary(&stats, number_channels, sampling_rate, out_path);
+        return Ok(());
+    }
+
+    let data = match gen_command {
+        GenCommands::Sine {
+            frequency,
+            frequency_right: None,
+            note,
+            vibrato_rate: Some(vibrato_rate),
+            vibrato_depth,
+            ..
+        } => {
+            let n_samples = acyclic_samples(&size, "sine with --vibrato-rate");
+            let frequency = note.unwrap_or(*frequency as f32);
+            check_nyquist(frequency, sampling_rate)?;
+
+            // The frequency deviates by up to the depth in cents (1/1200 of an octave)
+            let vibrato_at = |t: u32| {
+                let radians = 2. * PI * vibrato_rate * t as f32 / sampling_rate as f32;
+                frequency * 2f32.powf(vibrato_depth / 1200. * radians.sin())
+            };
+            gen_chirp(
+                vibrato_at,
+                n_samples,
+                number_channels,
+                volume,
+                sampling_rate,
+                &mut progress,
+            )?
+        }
+        GenCommands::Sine {
+            frequency,
+            phase,
+            frequency_right: None,
+            note,
+            vibrato_rate: None,
+            ..
+        } => {
+            let frequency = note.unwrap_or(*frequency as f32);
+            check_nyquist(frequency, sampling_rate)?;
+            let n_samples = periodic_samples(&size, frequency, sampling_rate, cli.max_duration)?;
+            let generated_samples =
+                loop_samples(cli.loop_period, &[frequency], n_samples, sampling_rate);
+            let wave = gen_sine_wave(
+                frequency,
+                phase.to_radians(),
+                generated_samples,
+                number_channels,
+                volume,
+                sampling_rate,
+                &mut progress,
+            )?;
+            repeat_frames(wave, n_samples, number_channels)
+        }
+        GenCommands::Sine {
+            frequency,
+            phase,
+            frequency_right: Some(frequency_right),
+            note,
+            ..
+        } => {
+            let n_samples = acyclic_samples(&size, "sine with --frequency-right");
+            if number_channels != 2 {
+                let mut cmd = Cli::command();
+                cmd.error(
+                    ErrorKind::ArgumentConflict,
+                    "--frequency-right can only be used with stereo",
+                )
+                .exit();
+            }
+            check_nyquist(note.unwrap_or(*frequency as f32), sampling_rate)?;
+            check_nyquist(*frequency_right as f32, sampling_rate)?;
+
+            // Each channel is generated in mono with its own frequency
+            let left = gen_sine_wave(
+                note.unwrap_or(*frequency as f32),
+                phase.to_radians(),
+                n_samples,
+                1,
+                volume,
+                sampling_rate,
+                &mut |_| {},
+            )?;
+            let right = gen_sine_wave(
+                *frequency_right as f32,
+                phase.to_radians(),
+                n_samples,
+                1,
+                volume,
+                sampling_rate,
+                &mut |_| {},
+            )?;
+            interleave_channels(&[left, right])?
+        }
+        GenCommands::Square {
+            frequency,
+            duty_cycle,
+        } => {
+            let n_samples =
+                periodic_samples(&size, *frequency as f32, sampling_rate, cli.max_duration)?;
+            let generated_samples = loop_samples(
+                cli.loop_period,
+                &[*frequency as f32],
+                n_samples,
+                sampling_rate,
+            );
+            let wave = gen_square_wave(
+                *frequency as f32,
+                *duty_cycle,
+                generated_samples,
+                number_channels,
+                volume,
+                sampling_rate,
+                &mut progress,
+            )?;
+            repeat_frames(wave, n_samples, number_channels)
+        }
+        GenCommands::Pulse {
+            frequency,
+            duty,
+            pwm_rate: None,
+            ..
+        } => {
+            let n_samples =
+                periodic_samples(&size, *frequency as f32, sampling_rate, cli.max_duration)?;
+            gen_square_wave(
+                *frequency as f32,
+                *duty,
+                n_samples,
+                number_channels,
+                volume,
+                sampling_rate,
+                &mut progress,
+            )?
+        }
+        GenCommands::Pulse {
+            frequency,
+            duty,
+            pwm_rate: Some(pwm_rate),
+            pwm_depth,
+        } => {
+            // The modulation means that the wave generally does not repeat
+            let n_samples = acyclic_samples(&size, "pulse with --pwm-rate");
+            gen_pulse_wave(
+                *frequency as f32,
+                Pwm {
+                    duty_cycle: *duty,
+                    rate: *pwm_rate,
+                    depth: *pwm_depth,
+                },
+                n_samples,
+                number_channels,
+                volume,
+                sampling_rate,
+                &mut progress,
+            )?
+        }
+        GenCommands::Triangle { frequency } => {
+            let n_samples =
+                periodic_samples(&size, *frequency as f32, sampling_rate, cli.max_duration)?;
+            let generated_samples = loop_samples(
+                cli.loop_period,
+                &[*frequency as f32],
+                n_samples,
+                sampling_rate,
+            );
+            let wave = gen_triangle_wave(
+                *frequency as f32,
+                generated_samples,
+                number_channels,
+                volume,
+                sampling_rate,
+                &mut progress,
+            )?;
+            repeat_frames(wave, n_samples, number_channels)
+        }
+        GenCommands::Sawtooth {
+            frequency,
+            descending,
+            band_limited: true,
+        } => {
+            check_nyquist(*frequency as f32, sampling_rate)?;
+            let n_samples =
+                periodic_samples(&size, *frequency as f32, sampling_rate, cli.max_duration)?;
+            gen_harmonics(
+                &band_limited_sawtooth(*frequency, *descending, sampling_rate),
+                n_samples,
+                number_channels,
+                volume,
+                sampling_rate,
+                cli.fast_sine,
+                cli.on_clip.into(),
+                &mut progress,
+            )?
+        }
+        GenCommands::Sawtooth {
+            frequency,
+            descending,
+            band_limited: false,
+        } => {
+            let n_samples =
+                periodic_samples(&size, *frequency as f32, sampling_rate, cli.max_duration)?;
+            gen_sawtooth_wave(
+                *frequency as f32,
+                *descending,
+                n_samples,
+                number_channels,
+                volume,
+                sampling_rate,
+                &mut progress,
+            )?
+        }
+        GenCommands::Sweep {
+            start,
+            finish,
+            sweep_return,
+            pre_hold_ms,
+            post_hold_ms,
+            log,
+        } => {
+            let n_samples = acyclic_samples(&size, "sweep");
+
+            if *log && (*start == 0 || *finish == 0) {
+                let mut cmd = Cli::command();
+                cmd.error(
+                    ErrorKind::InvalidValue,
+                    "The frequencies of a logarithmic sweep must be greater than 0",
+                )
+                .exit();
+            }
+            check_nyquist(*start as f32, sampling_rate)?;
+            check_nyquist(*finish as f32, sampling_rate)?;
+
+            if *sweep_return || *pre_hold_ms > 0 || *post_hold_ms > 0 || *log {
+                let profile = SweepProfile {
+                    start: *start as f32,
+                    finish: *finish as f32,
+                    pre_hold: ms_to_samples(*pre_hold_ms, sampling_rate),
+                    sweep: n_samples,
+                    post_hold: ms_to_samples(*post_hold_ms, sampling_rate),
+                    sweep_return: *sweep_return,
+                    log: *log,
+                };
+                gen_chirp(
+                    |t| profile.frequency_at(t),
+                    profile.number_samples(),
+                    number_channels,
+                    volume,
+                    sampling_rate,
+                    &mut progress,
+                )?
+            } else {
+                gen_sweep_wave(
+                    *start,
+                    *finish,
+                    n_samples,
+                    number_channels,
+                    volume,
+                    sampling_rate,
+                    &mut progress,
+                )?
+            }
+        }
+
+        GenCommands::AmpSweep {
+            frequency,
+            amp_start,
+            amp_end,
+        } => {
+            let n_samples = acyclic_samples(&size, "amp-sweep");
+
+            let mut data = gen_sine_wave(
+                *frequency as f32,
+                0.,
+                n_samples,
+                number_channels,
+                volume,
+                sampling_rate,
+                &mut progress,
+            )?;
+            apply_gain_ramp(&mut data, number_channels, *amp_start, *amp_end);
+            data
+        }
+
+        GenCommands::Harmonics {
+            infile,
+            no_normalize,
+            amplitude_db,
+        } => {
+            // With "-" the harmonics are read from stdin
+            let p = Path::new(infile);
+            let harmonics_read = if infile == "-" {
+                read_harmonics(std::io::stdin().lock(), *amplitude_db)
+            } else {
+                File::open(p)
+                    .map_err(|e| e.into())
+                    .and_then(|file| read_harmonics(BufReader::new(file), *amplitude_db))
+            };
+            let mut harmonics_set =
+                harmonics_read.map_err(|e| WavGenError::ReadError(p.to_path_buf(), e))?;
+            if !no_normalize {
+                normalise_harmonics(&mut harmonics_set);
+            }
+            for harmonic in &harmonics_set {
+                check_nyquist(harmonic.frequency, sampling_rate)?;
+            }
+
+            let n_samples = match size {
+                GeneratedSize::Cyclic => {
+                    let frequencies = harmonics_set
+                        .iter()
+                        .map(|h| h.frequency.round() as u32)
+                        .collect();
+                    let n_samples = sync_period(&frequencies, sampling_rate)?;
+                    // Checked before generating as the period can be very long
+                    check_duration(n_samples, sampling_rate, cli.max_duration)?;
+                    n_samples
+                }
+                GeneratedSize::NumberSamples(n_samples) => n_samples,
+            };
+
+            let frequencies: Vec<f32> = harmonics_set.iter().map(|h| h.frequency).collect();
+            let generated_samples =
+                loop_samples(cli.loop_period, &frequencies, n_samples, sampling_rate);
+            let wave = gen_harmonics(
+                &harmonics_set,
+                generated_samples,
+                number_channels,
+                volume,
+                sampling_rate,
+                cli.fast_sine,
+                cli.on_clip.into(),
+                &mut progress,
+            )?;
+            repeat_frames(wave, n_samples, number_channels)
+        }
+
+        GenCommands::Chord { notes } => {
+            let n_samples = acyclic_samples(&size, "chord");
+
+            let mut harmonics_set: Vec<Harmonic> = notes
+                .iter()
+                .map(|frequency| Harmonic {
+                    frequency: *frequency,
+                    amplitude: 1.,
+                    phase: 0.,
+                })
+                .collect();
+            normalise_harmonics(&mut harmonics_set);
+
+            gen_harmonics(
+                &harmonics_set,
+                n_samples,
+                number_channels,
+                volume,
+                sampling_rate,
+                cli.fast_sine,
+                cli.on_clip.into(),
+                &mut progress,
+            )?
+        }
+
+        GenCommands::Binaural { carrier, beat } => {
+            let n_samples = acyclic_samples(&size, "binaural");
+            if number_channels != 2 {
+                let mut cmd = Cli::command();
+                cmd.error(
+                    ErrorKind::ArgumentConflict,
+                    "binaural can only be used with stereo",
+                )
+                .exit();
+            }
+
+            // Each channel is generated independently with its own frequency
+            let left = gen_sine_wave(
+                *carrier,
+                0.,
+                n_samples,
+                1,
+                volume,
+                sampling_rate,
+                &mut progress,
+            )?;
+            let right = gen_sine_wave(
+                carrier + beat,
+                0.,
+                n_samples,
+                1,
+                volume,
+                sampling_rate,
+                &mut |_| {},
+            )?;
+            interleave_channels(&[left, right])?
+        }
+
+        GenCommands::Dtmf { digit } => {
+            let n_samples = acyclic_samples(&size, "dtmf");
+
+            let (row, column) = dtmf_frequencies(*digit);
+
+            let mut harmonics_set = vec![
+                Harmonic {
+                    frequency: row,
+                    amplitude: 1.,
+                    phase: 0.,
+                },
+                Harmonic {
+                    frequency: column,
+                    amplitude: 1.,
+                    phase: 0.,
+                },
+            ];
+            normalise_harmonics(&mut harmonics_set);
+
+            gen_harmonics(
+                &harmonics_set,
+                n_samples,
+                number_channels,
+                volume,
+                sampling_rate,
+                cli.fast_sine,
+                cli.on_clip.into(),
+                &mut progress,
+            )?
+        }
+
+        GenCommands::Partials {
+            fundamental,
+            partials,
+            inharmonicity,
+            rolloff,
+        } => {
+            let mut harmonics_set =
+                partial_series(*fundamental, *partials, *inharmonicity, *rolloff);
+            for (n, h) in (1..).zip(harmonics_set.iter()) {
+                eprintln!("Partial {}: {:.2}Hz", n, h.frequency);
+            }
+            normalise_harmonics(&mut harmonics_set);
+
+            let n_samples = match size {
+                // Stretched partials never come back into phase so only the harmonic series has a cycle
+                GeneratedSize::Cyclic if *inharmonicity == 0. => {
+                    (sampling_rate as f32 / fundamental).round() as u32
+                }
+                _ => acyclic_samples(&size, "partials with an inharmonicity"),
+            };
+
+            gen_harmonics(
+                &harmonics_set,
+                n_samples,
+                number_channels,
+                volume,
+                sampling_rate,
+                cli.fast_sine,
+                cli.on_clip.into(),
+                &mut progress,
+            )?
+        }
+
+        GenCommands::Wavetable {
+            frames,
+            frame_size,
+            from,
+            to,
+        } => {
+            acyclic_samples(&size, "wavetable");
+            check_duration(
+                frames.saturating_mul(*frame_size),
+                sampling_rate,
+                cli.max_duration,
+            )?;
+
+            eprintln!(
+                "Wavetable of {} frames with {} samples in each frame",
+                frames, frame_size
+            );
+            gen_wavetable(
+                *frames,
+                *frame_size,
+                *from,
+                *to,
+                number_channels,
+                volume,
+                sampling_rate,
+            )?
+        }
+
+        GenCommands::Impulse {
+            band_limited,
+            position,
+            width,
+        } => {
+            let n_samples = acyclic_samples(&size, "impulse");
+
+            if *position >= n_samples {
+                let mut cmd = Cli::command();
+                cmd.error(
+                    ErrorKind::InvalidValue,
+                    format!(
+                        "The position {} of the impulse is after the last sample of the wave ({})",
+                        position,
+                        n_samples.saturating_sub(1)
+                    ),
+                )
+                .exit();
+            }
+
+            if *band_limited {
+                eprintln!(
+                    "Band limited impulse with a bandwidth of {:.0}Hz",
+                    band_limited_impulse_bandwidth(n_samples, sampling_rate)
+                );
+                gen_band_limited_impulse(n_samples, number_channels, volume)
+            } else {
+                gen_impulse(n_samples, *position, *width, number_channels, volume)
+            }
+        }
+
+        GenCommands::Click {
+            bpm,
+            beats,
+            click_ms,
+            click_frequency,
+        } => {
+            let n_samples = acyclic_samples(&size, "click");
+            let accent_frequency = 2. * *click_frequency as f32;
+            check_nyquist(accent_frequency, sampling_rate)?;
+
+            // The clicks decay to silence so that they do not end with a click of their own
+            let click_samples = ms_to_samples(*click_ms, sampling_rate);
+            let gen_click = |frequency: f32| -> Result<Vec<i16>, WavGenError> {
+                let mut click = gen_sine_wave(
+                    frequency,
+                    0.,
+                    click_samples,
+                    number_channels,
+                    volume,
+                    sampling_rate,
+                    &mut |_| {},
+                )?;
+                apply_fade(&mut click, number_channels, 0, click_samples);
+                Ok(click)
+            };
+
+            gen_click_track(
+                &gen_click(*click_frequency as f32)?,
+                &gen_click(accent_frequency)?,
+                60. * sampling_rate as f64 / *bpm as f64,
+                *beats,
+                n_samples,
+                number_channels,
+            )
+        }
+
+        GenCommands::Noise => {
+            let n_samples = acyclic_samples(&size, "noise");
+
+            gen_white_noise(n_samples, number_channels, volume, &mut rng, &mut progress)
+        }
+
+        GenCommands::Silence => {
+            let n_samples = acyclic_samples(&size, "silence");
+
+            vec![0; (n_samples * number_channels as u32) as usize]
+        }
+
+        GenCommands::Multichannel { .. } => {
+            let n_samples = acyclic_samples(&size, "multichannel");
+
+            gen_multichannel(
+                channel_specs.as_deref().unwrap_or_default(),
+                n_samples,
+                volume,
+                sampling_rate,
+                &mut rng,
+            )?
+        }
+
+        GenCommands::Sequence { sequence_file } => {
+            acyclic_samples(&size, "sequence");
+
+            let segments = read_sequence(Path::new(sequence_file))?;
+            gen_sequence(&segments, number_channels, volume, sampling_rate, &mut rng)?
+        }
+
+        GenCommands::SteppedTone {
+            frequencies,
+            step_ms,
+            gap_ms,
+        } => {
+            acyclic_samples(&size, "stepped-tone");
+
+            gen_stepped_tone(
+                frequencies,
+                ms_to_samples(*step_ms, sampling_rate),
+                ms_to_samples(*gap_ms, sampling_rate),
+                number_channels,
+                volume,
+                sampling_rate,
+            )?
+        }
+    };
+    progress_bar.finish_and_clear();
+
+    let (mut data, number_channels) = match channel_delays {
+        Some(delays) => {
+            let delay_samples: Vec<u32> = delays
+                .iter()
+                .map(|delay| (delay * sampling_rate as f32 / 1000.).round() as u32)
+                .collect();
+            (delay_channels(&data, &delay_samples), output_channels)
+        }
+        None => (data, number_channels),
+    };
+
+    // Some waves determine their own length so check again
+    check_duration(
+        (data.len() / number_channels as usize) as u32,
+        sampling_rate,
+        cli.max_duration,
+    )?;
+
+    if cli.reverse {
+        reverse_frames(&mut data, number_channels);
+    }
+
+    if cli.zero_cross {
+        trim_to_zero_crossing(&mut data, number_channels);
+    }
+
+    if let Some(jitter_db) = cli.amplitude_jitter {
+        apply_amplitude_jitter(&mut data, jitter_db, &mut rng);
+    }
+
+    apply_fade(
+        &mut data,
+        number_channels,
+        ms_to_samples(cli.fade_in, sampling_rate),
+        ms_to_samples(cli.fade_out, sampling_rate),
+    );
+
+    if cli.attack.is_some() || cli.decay.is_some() || cli.sustain.is_some() || cli.release.is_some()
+    {
+        apply_adsr(
+            &mut data,
+            number_channels,
+            ms_to_samples(cli.attack.unwrap_or_default(), sampling_rate),
+            ms_to_samples(cli.decay.unwrap_or_default(), sampling_rate),
+            cli.sustain.unwrap_or(1.).clamp(0., 1.),
+            ms_to_samples(cli.release.unwrap_or_default(), sampling_rate),
+        );
+    }
+
+    if let Some(decay_tau) = cli.decay_tau {
+        apply_exp_decay(
+            &mut data,
+            number_channels,
+            decay_tau * sampling_rate as f32 / 1000.,
+        );
+    }
+
+    if let Some(tremolo_rate) = cli.tremolo_rate {
+        apply_tremolo(
+            &mut data,
+            number_channels,
+            tremolo_rate,
+            cli.tremolo_depth,
+            sampling_rate,
+        );
+    }
+
+    if let Some(pan) = cli.pan {
+        if number_channels != 2 {
+            let mut cmd = Cli::command();
+            cmd.error(
+                ErrorKind::ArgumentConflict,
+                "--pan can only be used with stereo",
+            )
+            .exit();
+        }
+        apply_pan(&mut data, pan.clamp(-1., 1.));
+    }
+
+    if let Some(preemphasis) = cli.preemphasis {
+        let biquad = match preemphasis {
+            Preemphasis::Us50 => Biquad::preemphasis(50e-6, sampling_rate),
+            Preemphasis::Us75 => Biquad::preemphasis(75e-6, sampling_rate),
+            Preemphasis::Riaa => Biquad::riaa_preemphasis(sampling_rate),
+        };
+        apply_biquad(&mut data, number_channels, &biquad);
+    }
+
+    if let Some(comb_delay) = cli.comb_delay {
+        let delay_samples = (comb_delay * sampling_rate as f32 / 1000.).round() as usize;
+        apply_comb(&mut data, delay_samples, cli.comb_feedback, number_channels);
+    }
+
+    if cli.normalize {
+        normalize_peak(&mut data, volume);
+    }
+
+    if let Some(dc_offset) = cli.dc_offset {
+        apply_dc_offset(&mut data, dc_offset, cli.on_clip.into())?;
+    }
+
+    if cli.limiter {
+        apply_soft_clip(&mut data, volume);
+    }
+
+    apply_window(&mut data, number_channels, cli.window);
+
+    if cli.lead_in > 0 || cli.lead_out > 0 {
+        data = pad_with_silence(
+            &data,
+            number_channels,
+            ms_to_samples(cli.lead_in, sampling_rate),
+            ms_to_samples(cli.lead_out, sampling_rate),
+        );
+    }
+
+    // The wave is analysed as it is written, so any stage that changes the samples goes above
+    analyse_wave(&cli, &data, number_channels, sampling_rate)?;
+
+    let out_path = Path::new(&cli.out_file_name);
+
+    if cli.dry_run {
+        print_dry_run(
+            &cli.command,
+            data.len(),
+            sampling_rate,
+            cli.band_split,
+            out_path,
+        );
+        return Ok(());
+    }
+
+    if cli.samples_only {
+        write_samples(&data)?;
+    } else if cli.band_split {
+        for band in octave_bands(sampling_rate) {
+            let mut band_data = data.clone();
+            apply_biquad(
+                &mut band_data,
+                number_channels,
+                &Biquad::highpass(band.lower, BUTTERWORTH_Q, sampling_rate),
+            );
+            apply_biquad(
+                &mut band_data,
+                number_channels,
+                &Biquad::lowpass(band.upper, BUTTERWORTH_Q, sampling_rate),
+            );
+
+            let band_path = band_file_path(out_path, &band);
+            println!("Band {:.1}Hz to {:.1}Hz", band.lower, band.upper);
+            let written = write_output(
+                &cli.command,
+                &band_data,
+                number_channels,
+                sampling_rate,
+                &band_path,
+                &mut rng,
+            )?;
+            print_finished(&written);
+            print_summary(
+                &SampleStats::of(&band_data),
+                number_channels,
+                sampling_rate,
+                &band_path,
+            );
+        }
+    } else {
+        let written = write_output(
+            &cli.command,
+            &data,
+            number_channels,
+            sampling_rate,
+            out_path,
+            &mut rng,
+        )?;
+        print_finished(&written);
+        print_summary(
+            &SampleStats::of(&data),
+            number_channels,
+            sampling_rate,
+            out_path,
+        );
+
+        // The same data can also be written in the other format
+        let also_written = match &cli.command {
+            OutputTypeCommands::Wav(wav_options) => match &wav_options.also_rust {
+                Some(rust_file_name) => {
+                    let rust_path = Path::new(rust_file_name);
+                    let mut rust_file = File::create(rust_path)
+                        .map_err(|e| WavGenError::CreateError(rust_path.to_path_buf(), e.into()))?;
+                    write_rust(
+                        &data,
+                        &ArrayOptions {
+                            name: "DATA",
+                            constants: false,
+                            columns: 10,
+                            const_array: false,
+                            split: false,
+                            sample_coding: SampleCoding::Signed,
+                        },
+                        sampling_rate,
+                        number_channels,
+                        rust_path,
+                        &mut rust_file,
+                    )?;
+                    vec![rust_path.to_path_buf()]
+                }
+                None => vec![],
+            },
+            OutputTypeCommands::Rust(rust_options)
+            | OutputTypeCommands::C(rust_options)
+            | OutputTypeCommands::Json(rust_options) => match &rust_options.also_wav {
+                Some(wav_file_name) => {
+                    let wav_path = Path::new(wav_file_name);
+                    write_wav(
+                        &data,
+                        BitDepth::Sixteen,
+                        number_channels,
+                        sampling_rate,
+                        false,
+                        false,
+                        wav_path,
+                    )?;
+                    vec![wav_path.to_path_buf()]
+                }
+                None => vec![],
+            },
+            OutputTypeCommands::Raw(_) | OutputTypeCommands::Convert(_) => vec![],
+        };
+        print_finished(&also_written);
+    }
+
+    // Saved after writing the output as the dither also uses the random number generator
+    if let Some(state_file) = &cli.rng_state_out {
+        rng.write_state(Path::new(state_file))?;
+    }
+
+    Ok(())
+}
+
+/// Reports the loudness, shows the preview and checks for silence of the `data` as requested by
+/// the `cli`. This needs to be done after all the stages that change the samples, e.g. `--window`,
+/// `--normalize` and `--lead-in`, so that it describes the wave that is written.
+fn analyse_wave(
+    cli: &Cli,
+    data: &[i16],
+    number_channels: u8,
+    sampling_rate: u32,
+) -> Result<(), WavGenError> {
+    if cli.lufs {
+        match integrated_loudness(data, number_channels, sampling_rate) {
+            Some(lufs) => eprintln!("Integrated loudness: {:.1} LUFS", lufs),
+            None => eprintln!("Integrated loudness: -inf LUFS"),
+        }
+    }
+
+    if cli.preview {
+        print_preview(data, number_channels, sampling_rate);
+    }
+
+    if let Some(threshold) = cli.fail_on_silence {
+        let peak = peak_dbfs(data);
+        if peak < threshold {
+            return Err(WavGenError::Silent(peak, threshold));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that `number_samples` (per channel) does not exceed the maximum duration
+fn check_duration(
+    number_samples: u32,
+    sampling_rate: u32,
+    max_duration: u32,
+) -> Result<(), WavGenError> {
+    if number_samples as u64 > max_duration as u64 * sampling_rate as u64 {
+        Err(WavGenError::MaxDurationExceeded(max_duration))
+    } else {
+        Ok(())
+    }
+}
+
+/// The level of the largest sample in dB relative to full scale. Silence is `-inf`
+fn peak_dbfs(data: &[i16]) -> f32 {
+    let peak = data
+        .iter()
+        .map(|s| s.unsigned_abs())
+        .max()
+        .unwrap_or_default();
+    dbfs(peak)
+}
+
+/// The level of a sample with the magnitude `peak` in dB relative to full scale
+fn dbfs(peak: u16) -> f32 {
+    20. * (peak as f32 / i16::MAX as f32).log10()
+}
+
+/// Reports each of the files that have been written
+fn print_finished(out_paths: &[PathBuf]) {
+    // Nothing is printed when writing to stdout as it would corrupt the output
+    for out_path in out_paths.iter().filter(|out_path| !is_stdout(out_path)) {
+        bunt::println!(
+            "{$bold+green}Finished{/$} writing to {}",
+            out_path.display()
+        );
+    }
+}
+
+/// The statistics of the written samples that are reported when finished
+#[derive(Default)]
+struct SampleStats {
+    number_samples: usize,
+    peak: u16,
+    // Samples at the largest or smallest value, which are most likely clipped
+    full_scale: usize,
+}
+
+impl SampleStats {
+    /// The statistics of all of the `data`
+    fn of(data: &[i16]) -> SampleStats {
+        let mut stats = SampleStats::default();
+        stats.add(data);
+        stats
+    }
+
+    /// Adds the `samples` to the statistics
+    fn add(&mut self, samples: &[i16]) {
+        self.number_samples += samples.len();
+        for sample in samples {
+            self.peak = self.peak.max(sample.unsigned_abs());
+            if *sample == i16::MAX || *sample == i16::MIN {
+                self.full_scale += 1;
+            }
+        }
+    }
+}
+
+/// Reports each of the files that would be written with the `command` for `number_samples`
+/// samples, without writing them
+fn print_dry_run(
+    command: &OutputTypeCommands,
+    number_samples: usize,
+    sampling_rate: u32,
+    band_split: bool,
+    out_path: &Path,
+) {
+    if band_split {
+        for band in octave_bands(sampling_rate) {
+            let band_path = band_file_path(out_path, &band);
+            for (path, format) in output_files(command, &band_path) {
+                print_would_write(&path, &format, number_samples);
+            }
+        }
+        return;
+    }
+
+    for (path, format) in output_files(command, out_path) {
+        print_would_write(&path, &format, number_samples);
+    }
+
+    // The files written in the other format with the same data
+    match command {
+        OutputTypeCommands::Wav(wav_options) => {
+            if let Some(rust_file_name) = &wav_options.also_rust {
+                print_would_write(Path::new(rust_file_name), "rust data array", number_samples);
+            }
+        }
+        OutputTypeCommands::Rust(rust_options)
+        | OutputTypeCommands::C(rust_options)
+        | OutputTypeCommands::Json(rust_options) => {
+            if let Some(wav_file_name) = &rust_options.also_wav {
+                print_would_write(Path::new(wav_file_name), "16 bit wav file", number_samples);
+            }
+        }
+        OutputTypeCommands::Raw(_) | OutputTypeCommands::Convert(_) => {}
+    }
+}
+
+/// Reports that `number_samples` samples would be written to `out_path` in the `format`
+fn print_would_write(out_path: &Path, format: &str, number_samples: usize) {
+    let out_name = if is_stdout(out_path) {
+        "stdout".to_string()
+    } else {
+        out_path.display().to_string()
+    };
+    bunt::println!(
+        "{$bold+cyan}Dry run{/$} would write {} samples to {} ({})",
+        number_samples,
+        out_name,
+        format
+    );
+}
+
+/// Reports the number of samples, the duration, the peak and any clipping of the wave written to
+/// `out_path`
+fn print_summary(stats: &SampleStats, number_channels: u8, sampling_rate: u32, out_path: &Path) {
+    // Nothing is printed when writing to stdout as it would corrupt the output
+    if is_stdout(out_path) {
+        return;
+    }
+
+    let samples_per_channel = stats.number_samples / number_channels as usize;
+    let samples = if number_channels == 1 {
+        format!("{} samples", stats.number_samples)
+    } else {
+        format!(
+            "{} samples ({} for each of {} channels)",
+            stats.number_samples, samples_per_channel, number_channels
+        )
+    };
+    let summary = format!(
+        "{}, {:.3}s, peak {} ({:.1}dBFS)",
+        samples,
+        samples_per_channel as f64 / sampling_rate as f64,
+        stats.peak,
+        dbfs(stats.peak)
+    );
+    if stats.full_scale == 0 {
+        println!("{}, no clipping", summary);
+    } else {
+        bunt::println!(
+            "{}, {$bold+yellow}{} samples at full scale{/$} (clipped)",
+            summary,
+            stats.full_scale
+        );
+    }
+}
+
+/// Waves with fewer samples than this are generated without a progress bar
+const PROGRESS_BAR_MIN_SAMPLES: u32 = 10_000_000;
+
+/// Creates a progress bar for generating `number_samples` samples. The progress bar is hidden for
+/// short waves and when stderr is not a terminal.
+fn new_progress_bar(number_samples: u32) -> ProgressBar {
+    if number_samples < PROGRESS_BAR_MIN_SAMPLES || !std::io::stderr().is_terminal() {
+        return ProgressBar::hidden();
+    }
+
+    let progress_bar = ProgressBar::new(number_samples as u64);
+    if let Ok(style) = ProgressStyle::with_template(
+        "{spinner} [{elapsed_precise}] [{bar:40}] {percent}% (eta {eta})",
+    ) {
+        progress_bar.set_style(style.progress_chars("=> "));
+    }
+    progress_bar
+}
+
+/// Returns the number of samples (per channel) of a wave with the `frequency`. With `--cycle` this
+/// is one cycle of the wave, which is checked against the maximum duration.
+fn periodic_samples(
+    size: &GeneratedSize,
+    frequency: f32,
+    sampling_rate: u32,
+    max_duration: u32,
+) -> Result<u32, WavGenError> {
+    match size {
+        GeneratedSize::Cyclic => {
+            // One cycle of frames, each with a sample for every channel
+            let n_samples = (sampling_rate as f32 / frequency).round() as u32;
+            check_duration(n_samples, sampling_rate, max_duration)?;
+            Ok(n_samples)
+        }
+        GeneratedSize::NumberSamples(n_samples) => Ok(*n_samples),
+    }
+}
+
+/// Returns the number of samples for the waveforms that have no cycle. If `--cycle` has been
+/// specified the program exits with an error.
+fn acyclic_samples(size: &GeneratedSize, subcommand: &str) -> u32 {
+    match size {
+        GeneratedSize::Cyclic => {
+            let mut cmd = Cli::command();
+            cmd.error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "Specifying --cycle for the subcommand {} is not meaningful",
+                    subcommand
+                ),
+            )
+            .exit();
+        }
+        GeneratedSize::NumberSamples(n_samples) => *n_samples,
+    }
+}
+
+/// Generate silence with an impulse of `volume` for `width` samples starting at the sample `position`.
+/// The impulse is cut off at the end of the wave.
+///
+/// # Arguments
+/// * `number_samples` - the number of samples to be generated.
+/// * `position` - The index of the first sample of the impulse
+/// * `width` - The number of samples of the impulse
+/// * `number_channels` - The number of channels
+/// * `volume`- The volume of the impulse
+fn gen_impulse(
+    number_samples: u32,
+    position: u32,
+    width: u32,
+    number_channels: u8,
+    volume: u16,
+) -> Vec<i16> {
+    let mut data = vec![0; (number_samples * number_channels as u32) as usize];
+    for sample in data
+        .iter_mut()
+        .skip((position * number_channels as u32) as usize)
+        .take((width * number_channels as u32) as usize)
+    {
+        *sample = volume as i16;
+    }
+
+    data
+}
+
+/// Generate a click track of silence with a click at the start of each beat. The first beat of each bar
+/// has the `accent` click instead. A click that is longer than a beat is cut off by the next click.
+///
+/// # Arguments
+/// * `click` - The samples of a click
+/// * `accent` - The samples of the click on the first beat of a bar
+/// * `beat_samples` - the number of samples in a beat, which need not be a whole number
+/// * `beats` - The number of beats in a bar
+/// * `number_samples` - the number of samples to be generated.
+/// * `number_channels` - The number of channels
+fn gen_click_track(
+    click: &[i16],
+    accent: &[i16],
+    beat_samples: f64,
+    beats: u32,
+    number_samples: u32,
+    number_channels: u8,
+) -> Vec<i16> {
+    let mut data = vec![0; (number_samples * number_channels as u32) as usize];
+    let length = data.len();
+
+    // Rounding each beat separately stops the rounding errors adding up over a long track
+    let beat_starts = (0..)
+        .map(|beat| (beat as f64 * beat_samples).round() as usize * number_channels as usize)
+        .take_while(|start| *start < length);
+    for (beat, start) in beat_starts.enumerate() {
+        let samples = if beat % beats as usize == 0 {
+            accent
+        } else {
+            click
+        };
+        let end = (start + samples.len()).min(length);
+        data[start..end].copy_from_slice(&samples[..end - start]);
+    }
+
+    data
+}
+
+/// Number of zero crossings either side of the centre of the band limited impulse
+const SINC_ZERO_CROSSINGS: u32 = 32;
+
+/// Generate a band limited impulse as a set of `i16` samples. This is a sinc, cut off at the Nyquist frequency,
+/// centred on the sample `number_samples / 2` and tapered with a Blackman window over
+/// `sinc_half_width` samples either side. The centre is always on a sample, also for an even number of
+/// samples, so that the peak is the full volume and the spectrum is flat.
+///
+/// # Arguments
+/// * `number_samples` - the number of samples to be generated.
+/// * `number_channels` - The number of channels
+/// * `volume`- The volume of the impulse
+fn gen_band_limited_impulse(number_samples: u32, number_channels: u8, volume: u16) -> Vec<i16> {
+    let mut data = Vec::<i16>::new();
+
+    let centre = number_samples / 2;
+    let half_width = sinc_half_width(number_samples);
+
+    for t in 0..number_samples {
+        let x = t as f32 - centre as f32;
+        let value = if x.abs() >= half_width {
+            0.
+        } else {
+            sinc(x) * blackman(x / half_width)
+        };
+        let amplitude = (value * volume as f32) as i16;
+
+        for _ in 0..number_channels {
+            data.push(amplitude);
+        }
+    }
+
+    data
+}
+
+/// The number of samples either side of the centre of a band limited impulse of `number_samples`
+/// samples over which the sinc is windowed
+fn sinc_half_width(number_samples: u32) -> f32 {
+    SINC_ZERO_CROSSINGS.min(number_samples / 2 + 1) as f32
+}
+
+/// The bandwidth in hertz of a band limited impulse of `number_samples` samples. The Blackman
+/// window widens the cut off at the Nyquist frequency into a transition band of about
+/// `4 / window length` of the `sampling_rate` (see "The Scientist and Engineer's Guide to Digital
+/// Signal Processing", chapter 16), so the impulse is flat up to the start of this band.
+fn band_limited_impulse_bandwidth(number_samples: u32, sampling_rate: u32) -> f32 {
+    let window_length = 2. * sinc_half_width(number_samples);
+    let transition = 4. / window_length * sampling_rate as f32;
+    (sampling_rate as f32 - transition) / 2.
+}
+
+/// The normalised sinc function `sin(πx) / πx`
+fn sinc(x: f32) -> f32 {
+    if x == 0. {
+        1.
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// The Blackman window at position `x` from -1.0 to 1.0, with 1.0 in the centre at 0.0
+fn blackman(x: f32) -> f32 {
+    // Convert to the usual form where the window runs from 0 to 1
+    let n = (x + 1.) / 2.;
+    0.42 - 0.5 * (2. * PI * n).cos() + 0.08 * (4. * PI * n).cos()
+}
+
+/// Generate white noise as a set of `i16` samples that are uniformly distributed between `-volume` and `volume`
+/// (limited to the range of `i16`).
+///
+/// # Arguments
+/// * `number_samples` - the number of samples to be generated.
+/// * `number_channels` - The number of channels
+/// * `volume`- The volume of the generated noise
+/// * `rng` - The random number generator. This is left in a state to continue the noise.
+/// * `progress` - Called with the number of samples generated so far (see `report_progress`)
+fn gen_white_noise(
+    number_samples: u32,
+    number_channels: u8,
+    volume: u16,
+    rng: &mut Rng,
+    progress: &mut dyn FnMut(u32),
+) -> Vec<i16> {
+    let mut data = Vec::<i16>::new();
+
+    for t in 0..number_samples {
+        let amplitude = rng
+            .gen_range(-(volume as i32)..=volume as i32)
+            .clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+
+        for _ in 0..number_channels {
+            data.push(amplitude);
+        }
+        report_progress(t + 1, number_samples, progress);
+    }
+
+    data
+}
+
+/// Generate a wave where each channel has its own content and returns it as a set of interleaved `i16` samples.
+///
+/// # Arguments
+/// * `channel_specs` - The content of each channel. The number of channels is the number of specifications.
+/// * `number_samples` - the number of samples to be generated for each channel.
+/// * `volume`- The volume of the generated wave
+/// * `sampling_rate`- The rate at which the wave wave is sampled, e.g 44100 hertz.
+/// * `rng` - The random number generator used for the noise channels
+fn gen_multichannel(
+    channel_specs: &[ChannelSpec],
+    number_samples: u32,
+    volume: u16,
+    sampling_rate: u32,
+    rng: &mut Rng,
+) -> Result<Vec<i16>, WavGenError> {
+    let channels: Vec<Vec<i16>> = channel_specs
+        .iter()
+        .map(|spec| {
+            Ok(match spec {
+                ChannelSpec::Sine(frequency) => gen_sine_wave(
+                    *frequency as f32,
+                    0.,
+                    number_samples,
+                    1,
+                    volume,
+                    sampling_rate,
+                    &mut |_| {},
+                )?,
+                ChannelSpec::Noise => gen_white_noise(number_samples, 1, volume, rng, &mut |_| {}),
+                ChannelSpec::Silence => vec![0; number_samples as usize],
+            })
+        })
+        .collect::<Result<_, WavGenError>>()?;
+
+    interleave_channels(&channels)
+}
+
+/// Interleaves the samples of the separately generated channels so that each frame
+/// has a sample from each of the channels in turn.
+fn interleave_channels(channels: &[Vec<i16>]) -> Result<Vec<i16>, WavGenError> {
+    let number_samples = channels.first().ok_or(WavGenError::NoChannels)?.len();
+    if channels.iter().any(|c| c.len() != number_samples) {
+        return Err(WavGenError::ChannelLengthMismatch);
+    }
+
+    let mut data = Vec::<i16>::with_capacity(number_samples * channels.len());
+    for i in 0..number_samples {
+        for channel in channels {
+            data.push(channel[i]);
+        }
+    }
+
+    Ok(data)
+}
+
+/// Copy the mono wave in `data` to a channel for each delay, each delayed by its number of samples.
+/// The start of a delayed channel is filled with silence and the length of the wave stays the same.
+/// The samples of the channels are interleaved.
+fn delay_channels(data: &[i16], delay_samples: &[u32]) -> Vec<i16> {
+    let mut delayed = Vec::<i16>::with_capacity(data.len() * delay_samples.len());
+
+    for t in 0..data.len() {
+        for delay in delay_samples {
+            let sample = match t.checked_sub(*delay as usize) {
+                Some(i) => data[i],
+                None => 0,
+            };
+            delayed.push(sample);
+        }
+    }
+
+    delayed
+}
+
+/// Generate a wavetable of single cycle frames where the spectrum is interpolated from the `from`
+/// spectrum in the first frame to the `to` spectrum in the last frame. The frames are concatenated.
+///
+/// # Arguments
+/// * `frames` - The number of frames
+/// * `frame_size` - The number of samples in each frame
+/// * `from` - The spectrum of the first frame
+/// * `to` - The spectrum of the last frame
+/// * `number_channels` - The number of channels
+/// * `volume`- The volume of the generated wave
+/// * `sampling_rate`- The rate at which the wave wave is sampled, e.g 44100 hertz.
+fn gen_wavetable(
+    frames: u32,
+    frame_size: u32,
+    from: Spectrum,
+    to: Spectrum,
+    number_channels: u8,
+    volume: u16,
+    sampling_rate: u32,
+) -> Result<Vec<i16>, WavGenError> {
+    // The frequency at which exactly one cycle fits in a frame
+    let fundamental = sampling_rate as f32 / frame_size as f32;
+
+    let mut segments = Vec::<Vec<i16>>::new();
+    for frame in 0..frames {
+        let position = if frames > 1 {
+            frame as f32 / (frames - 1) as f32
+        } else {
+            0.
+        };
+
+        // Only the harmonics below the Nyquist frequency of the frame
+        let mut harmonics_set: Vec<Harmonic> = (1..frame_size / 2)
+            .map(|n| Harmonic {
+                frequency: n as f32 * fundamental,
+                amplitude: (1. - position) * from.amplitude(n) + position * to.amplitude(n),
+                phase: 0.,
+            })
+            .filter(|h| h.amplitude > 0.)
+            .collect();
+        normalise_harmonics(&mut harmonics_set);
+
+        segments.push(gen_harmonics(
+            &harmonics_set,
+            frame_size,
+            number_channels,
+            volume,
+            sampling_rate,
+            false,
+            OnClip::Saturate,
+            &mut |_| {},
+        )?);
+    }
+
+    Ok(concat_segments(&segments))
+}
+
+/// Generate a sequence of steady sine waves, one for each frequency, and returns it as a set of `i16` samples.
+///
+/// # Arguments
+/// * `frequencies` - The frequencies of the tones in hertz, in the order they are generated
+/// * `step_samples` - The number of samples each tone is held for
+/// * `gap_samples` - The number of samples of silence between the tones
+/// * `number_channels` - The number of channels
+/// * `volume`- The volume of the generated wave
+/// * `sampling_rate`- The rate at which the wave wave is sampled, e.g 44100 hertz.
+fn gen_stepped_tone(
+    frequencies: &[u32],
+    step_samples: u32,
+    gap_samples: u32,
+    number_channels: u8,
+    volume: u16,
+    sampling_rate: u32,
+) -> Result<Vec<i16>, WavGenError> {
+    let gap = vec![0; (gap_samples * number_channels as u32) as usize];
+
+    let mut segments = Vec::<Vec<i16>>::new();
+    for (i, frequency) in frequencies.iter().enumerate() {
+        if i > 0 && gap_samples > 0 {
+            segments.push(gap.clone());
+        }
+        segments.push(gen_sine_wave(
+            *frequency as f32,
+            0.,
+            step_samples,
+            number_channels,
+            volume,
+            sampling_rate,
+            &mut |_| {},
+        )?);
+    }
+
+    Ok(concat_segments(&segments))
+}
+
+/// Reads the harmonics as csv from the `reader`, e.g. a file or stdin. With `amplitude_db` the
+/// amplitudes are read as dBFS and converted to ratios.
+fn read_harmonics<R: Read>(reader: R, amplitude_db: bool) -> Result<Vec<Harmonic>, Box<dyn Error>> {
+    // Flexible so that the phase can be left out. Lines starting with # are comments.
+    let mut rdr = csv::ReaderBuilder::new()
+        .flexible(true)
+        .comment(Some(b'#'))
+        .from_reader(reader);
+    let mut harmonics = Vec::<Harmonic>::new();
+
+    for result in rdr.records() {
+        let record = result?;
+
+        // Skip lines that only contain whitespace
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+
+        // The line in the file, counting the header as line 1
+        let line_number = record
+            .position()
+            .map_or(0, |position| position.line() as usize);
+
+        let f = parse_harmonic_field(&record, 0, "frequency", line_number)?;
+        let mut a = parse_harmonic_field(&record, 1, "amplitude", line_number)?;
+        if amplitude_db {
+            a = 10f32.powf(a / 20.);
+        }
+
+        // The phase is optional
+        let p = match record.get(2) {
+            Some(_) => parse_harmonic_field(&record, 2, "phase", line_number)?,
+            None => 0.,
+        };
+
+        harmonics.push(Harmonic {
+            frequency: f,
+            amplitude: a,
+            phase: p,
+        });
+    }
+
+    Ok(harmonics)
+}
+
+/// Parses the field at `index` of a harmonic `record`. A missing field is reported as an empty text.
+fn parse_harmonic_field(
+    record: &csv::StringRecord,
+    index: usize,
+    field: &'static str,
+    line_number: usize,
+) -> Result<f32, WavGenError> {
+    let text = record.get(index).unwrap_or_default();
+    text.trim()
+        .parse()
+        .map_err(|_| WavGenError::HarmonicParseError(line_number, field, text.to_string()))
+}
+
+/// Reads the content of each channel from the file at `channels_path`. Each line specifies a channel
+/// in order. Empty lines and lines starting with `#` are ignored.
+fn read_channel_specs(channels_path: &Path) -> Result<Vec<ChannelSpec>, WavGenError> {
+    let contents = std::fs::read_to_string(channels_path)
+        .map_err(|e| WavGenError::ReadError(channels_path.to_path_buf(), e.into()))?;
+
+    let mut channel_specs = Vec::<ChannelSpec>::new();
+
+    for (line_number, line) in (1..).zip(contents.lines()) {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let spec = match fields[..] {
+            ["sine", frequency] => ChannelSpec::Sine(
+                frequency
+                    .parse()
+                    .map_err(|_| WavGenError::ChannelSpecParseError(line_number))?,
+            ),
+            ["noise"] => ChannelSpec::Noise,
+            ["silence"] => ChannelSpec::Silence,
+            _ => return Err(WavGenError::ChannelSpecParseError(line_number)),
+        };
+
+        // The number of channels has to fit in the wav header
+        if channel_specs.len() == u8::MAX as usize {
+            return Err(WavGenError::ChannelSpecParseError(line_number));
+        }
+        channel_specs.push(spec);
+    }
+
+    if channel_specs.is_empty() {
+        return Err(WavGenError::NoChannels);
+    }
+
+    Ok(channel_specs)
+}
+
+/// Reads the segments of a sequence from the file at `sequence_path`. Empty lines and lines
+/// starting with `#` are ignored.
+fn read_sequence(sequence_path: &Path) -> Result<Vec<Segment>, WavGenError> {
+    let contents = std::fs::read_to_string(sequence_path)
+        .map_err(|e| WavGenError::ReadError(sequence_path.to_path_buf(), e.into()))?;
+
+    let mut segments = Vec::<Segment>::new();
+
+    for (line_number, line) in (1..).zip(contents.lines()) {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let parse_error = || WavGenError::SequenceParseError(line_number);
+        let frequency = |s: &str| s.parse::<u32>().map_err(|_| parse_error());
+        let duration = |s: &str| parse_segment_duration(s).ok_or_else(parse_error);
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let segment = match fields[..] {
+            ["sine", f, d] => Segment::Sine(frequency(f)?, duration(d)?),
+            ["square", f, d] => Segment::Square(frequency(f)?, duration(d)?),
+            ["triangle", f, d] => Segment::Triangle(frequency(f)?, duration(d)?),
+            ["sawtooth", f, d] => Segment::Sawtooth(frequency(f)?, duration(d)?),
+            ["sweep", start, finish, d] => {
+                Segment::Sweep(frequency(start)?, frequency(finish)?, duration(d)?)
+            }
+            ["noise", d] => Segment::Noise(duration(d)?),
+            ["silence", d] => Segment::Silence(duration(d)?),
+            _ => return Err(parse_error()),
+        };
+        segments.push(segment);
+    }
+
+    if segments.is_empty() {
+        return Err(WavGenError::NoSegments);
+    }
+
+    Ok(segments)
+}
+
+/// Parses the duration of a segment, e.g. `1.5s` or `250ms`, and returns it in seconds
+fn parse_segment_duration(s: &str) -> Option<f32> {
+    let seconds = if let Some(ms) = s.strip_suffix("ms") {
+        ms.parse::<f32>().ok()? / 1000.
+    } else {
+        s.strip_suffix('s')?.parse::<f32>().ok()?
+    };
+
+    if seconds >= 0. && seconds.is_finite() {
+        Some(seconds)
+    } else {
+        None
+    }
+}
+
+/// Generate each segment of a sequence with the existing generators and concatenate them.
+///
+/// # Arguments
+/// * `segments` - The segments in the order they are played
+/// * `number_channels` - The number of channels
+/// * `volume`- The volume of the generated wave
+/// * `sampling_rate`- The rate at which the wave wave is sampled, e.g 44100 hertz.
+/// * `rng` - The random number generator for the noise segments
+fn gen_sequence(
+    segments: &[Segment],
+    number_channels: u8,
+    volume: u16,
+    sampling_rate: u32,
+    rng: &mut Rng,
+) -> Result<Vec<i16>, WavGenError> {
+    let to_samples = |duration: f32| (duration * sampling_rate as f32).round() as u32;
+
+    let generated: Vec<Vec<i16>> = segments
+        .iter()
+        .map(|segment| {
+            Ok(match segment {
+                Segment::Sine(frequency, duration) => gen_sine_wave(
+                    *frequency as f32,
+                    0.,
+                    to_samples(*duration),
+                    number_channels,
+                    volume,
+                    sampling_rate,
+                    &mut |_| {},
+                )?,
+                Segment::Square(frequency, duration) => gen_square_wave(
+                    *frequency as f32,
+                    0.5,
+                    to_samples(*duration),
+                    number_channels,
+                    volume,
+                    sampling_rate,
+                    &mut |_| {},
+                )?,
+                Segment::Triangle(frequency, duration) => gen_triangle_wave(
+                    *frequency as f32,
+                    to_samples(*duration),
+                    number_channels,
+                    volume,
+                    sampling_rate,
+                    &mut |_| {},
+                )?,
+                Segment::Sawtooth(frequency, duration) => gen_sawtooth_wave(
+                    *frequency as f32,
+                    false,
+                    to_samples(*duration),
+                    number_channels,
+                    volume,
+                    sampling_rate,
+                    &mut |_| {},
+                )?,
+                Segment::Sweep(start, finish, duration) => gen_sweep_wave(
+                    *start,
+                    *finish,
+                    to_samples(*duration),
+                    number_channels,
+                    volume,
+                    sampling_rate,
+                    &mut |_| {},
+                )?,
+                Segment::Noise(duration) => gen_white_noise(
+                    to_samples(*duration),
+                    number_channels,
+                    volume,
+                    rng,
+                    &mut |_| {},
+                ),
+                Segment::Silence(duration) => {
+                    vec![0; (to_samples(*duration) * number_channels as u32) as usize]
+                }
+            })
+        })
+        .collect::<Result<_, WavGenError>>()?;
 
-        /// The finishing frequency in hertz
-        #[clap(short, long, value_parser, default_value = "2000")]
-        finish: u32,
-    },
+    Ok(concat_segments(&generated))
+}
 
-    /// Generate a wave that combines the sine waves specified in a external csv file.
-    Harmonics {
-        /// Name of the csv file containing the harmonics
-        #[clap(short, long, default_value_t = String::from("harmonics.csv"),value_parser)]
-        infile: String,
-    },
+/// Creates the partials of the `fundamental` frequency with the amplitude of the nth partial given
+/// by the `rolloff`. The partial frequencies are stretched by the `inharmonicity` coefficient B as
+/// `f_n = n * fundamental * sqrt(1 + B * n^2)`. With B as 0 this is the harmonic series.
+fn partial_series(
+    fundamental: f32,
+    partials: u32,
+    inharmonicity: f32,
+    rolloff: Rolloff,
+) -> Vec<Harmonic> {
+    (1..=partials)
+        .map(|n| {
+            let n = n as f32;
+            Harmonic {
+                frequency: n * fundamental * (1. + inharmonicity * n * n).sqrt(),
+                amplitude: rolloff.amplitude(n),
+                phase: 0.,
+            }
+        })
+        .collect()
+}
+
+/// The peak of the sum of `sin(n x) / n` over all n, Si(π), which includes the overshoot
+/// of the Gibbs phenomenon at the jump of a sawtooth wave
+const SAWTOOTH_PEAK: f32 = 1.851_937;
+
+/// Creates the harmonics of a sawtooth wave of `frequency` hertz that are below the Nyquist frequency.
+/// The amplitude of the nth harmonic is 1/n, scaled so that the peak of the wave is about 1.
+/// The `frequency` needs to have been checked with `check_nyquist`, so that it is not 0.
+fn band_limited_sawtooth(frequency: u32, descending: bool, sampling_rate: u32) -> Vec<Harmonic> {
+    // The largest n with n * frequency below half the sampling rate
+    let harmonics = (sampling_rate - 1) / (2 * frequency);
+
+    (1..=harmonics)
+        .map(|n| Harmonic {
+            frequency: (n * frequency) as f32,
+            amplitude: 1. / (n as f32 * SAWTOOTH_PEAK),
+            // The series of sin(n x) / n ramps down so it is inverted for a rising ramp
+            phase: if descending { 0. } else { 180. },
+        })
+        .collect()
+}
+
+/// Normalise the amplitudes of the harmonics so that the sum of them all is 1
+fn normalise_harmonics(harmonics_set: &mut [Harmonic]) {
+    let mut sum = 0.;
+    for h in harmonics_set.iter_mut() {
+        sum += h.amplitude;
+    }
+
+    for h in harmonics_set.iter_mut() {
+        h.amplitude /= sum;
+    }
+}
+
+/// Writes the `data` to the file at `out_path` in the format selected by the output type command.
+/// The `rng` is used for the dither. Returns the paths of the files written.
+fn write_output(
+    command: &OutputTypeCommands,
+    data: &[i16],
+    number_channels: u8,
+    sampling_rate: u32,
+    out_path: &Path,
+    rng: &mut Rng,
+) -> Result<Vec<PathBuf>, WavGenError> {
+    match command {
+        OutputTypeCommands::Wav(wav_options) => {
+            if wav_options.bit_depths.is_empty() {
+                let bit_depth = match wav_options.format {
+                    SampleFormat::Int => wav_options.bit_depth,
+                    SampleFormat::Float => BitDepth::Float,
+                };
+                write_wav(
+                    &dithered(data, bit_depth, wav_options.dither, rng),
+                    bit_depth,
+                    number_channels,
+                    sampling_rate,
+                    wav_options.rifx,
+                    wav_options.rf64,
+                    out_path,
+                )?;
+                return Ok(vec![out_path.to_path_buf()]);
+            }
+
+            // Write each bit depth to its own file from the same data
+            let mut out_paths = Vec::<PathBuf>::new();
+            for bit_depth in &wav_options.bit_depths {
+                let depth_path = suffixed_file_path(out_path, &format!("{}bit", bit_depth.bits()));
+                write_wav(
+                    &dithered(data, *bit_depth, wav_options.dither, rng),
+                    *bit_depth,
+                    number_channels,
+                    sampling_rate,
+                    wav_options.rifx,
+                    wav_options.rf64,
+                    &depth_path,
+                )?;
+                out_paths.push(depth_path);
+            }
+            Ok(out_paths)
+        }
+        OutputTypeCommands::Rust(rust_options) => {
+            let mut out_file = File::create(out_path)
+                .map_err(|e| WavGenError::CreateError(out_path.to_path_buf(), e.into()))?;
+            write_rust(
+                data,
+                &ArrayOptions::from(rust_options),
+                sampling_rate,
+                number_channels,
+                out_path,
+                &mut out_file,
+            )?;
+            Ok(vec![out_path.to_path_buf()])
+        }
+        OutputTypeCommands::C(c_options) => {
+            let mut out_file = File::create(out_path)
+                .map_err(|e| WavGenError::CreateError(out_path.to_path_buf(), e.into()))?;
+            write_c_header(
+                data,
+                &ArrayOptions::from(c_options),
+                sampling_rate,
+                number_channels,
+                out_path,
+                &mut out_file,
+            )?;
+            Ok(vec![out_path.to_path_buf()])
+        }
+        OutputTypeCommands::Json(json_options) => {
+            let out_file = File::create(out_path)
+                .map_err(|e| WavGenError::CreateError(out_path.to_path_buf(), e.into()))?;
+            write_json(
+                data,
+                sampling_rate,
+                number_channels,
+                sample_coding(
+                    json_options.unsigned,
+                    json_options.dac_bits,
+                    json_options.encoding,
+                ),
+                out_path,
+                out_file,
+            )?;
+            Ok(vec![out_path.to_path_buf()])
+        }
+        OutputTypeCommands::Raw(raw_options) => {
+            let out_file = File::create(out_path)
+                .map_err(|e| WavGenError::CreateError(out_path.to_path_buf(), e.into()))?;
+            write_raw(
+                data,
+                raw_options.endian,
+                sample_coding(
+                    raw_options.unsigned,
+                    raw_options.dac_bits,
+                    raw_options.encoding,
+                ),
+                out_path,
+                out_file,
+            )?;
+            Ok(vec![out_path.to_path_buf()])
+        }
+        OutputTypeCommands::Convert(_) => unreachable!("converted before generating"),
+    }
+}
+
+/// The files that `write_output` writes for the `command` at `out_path`, each with a
+/// description of its format
+fn output_files(command: &OutputTypeCommands, out_path: &Path) -> Vec<(PathBuf, String)> {
+    match command {
+        OutputTypeCommands::Wav(wav_options) => {
+            let container = if wav_options.rifx {
+                "RIFX"
+            } else if wav_options.rf64 {
+                "RF64"
+            } else {
+                "wav"
+            };
+            let format = |bit_depth: BitDepth| match bit_depth {
+                BitDepth::Float => format!("32 bit float {} file", container),
+                _ => format!("{} bit {} file", bit_depth.bits(), container),
+            };
+
+            if wav_options.bit_depths.is_empty() {
+                let bit_depth = match wav_options.format {
+                    SampleFormat::Int => wav_options.bit_depth,
+                    SampleFormat::Float => BitDepth::Float,
+                };
+                return vec![(out_path.to_path_buf(), format(bit_depth))];
+            }
+            wav_options
+                .bit_depths
+                .iter()
+                .map(|bit_depth| {
+                    let depth_path =
+                        suffixed_file_path(out_path, &format!("{}bit", bit_depth.bits()));
+                    (depth_path, format(*bit_depth))
+                })
+                .collect()
+        }
+        OutputTypeCommands::Rust(_) => {
+            vec![(out_path.to_path_buf(), "rust data array".to_string())]
+        }
+        OutputTypeCommands::C(_) => vec![(out_path.to_path_buf(), "C header".to_string())],
+        OutputTypeCommands::Json(_) => vec![(out_path.to_path_buf(), "JSON".to_string())],
+        OutputTypeCommands::Raw(_) => vec![(out_path.to_path_buf(), "raw samples".to_string())],
+        OutputTypeCommands::Convert(_) => unreachable!("converted before generating"),
+    }
+}
+
+/// Reads the wav file given in the `convert_options` and writes its samples as a rust data
+/// structure to the file at `out_path`. With `dry_run` the file is only reported, not written
+fn convert_wav(
+    convert_options: &ConvertOptions,
+    out_path: &Path,
+    dry_run: bool,
+) -> Result<(), WavGenError> {
+    let in_path = Path::new(&convert_options.infile);
+    let mut in_file =
+        File::open(in_path).map_err(|e| WavGenError::ReadError(in_path.to_path_buf(), e.into()))?;
+    let (header, track) = wav::read(&mut in_file)
+        .map_err(|e| WavGenError::ReadError(in_path.to_path_buf(), e.into()))?;
+    let data = dequantize(track)
+        .ok_or_else(|| WavGenError::ReadError(in_path.to_path_buf(), "no samples found".into()))?;
+
+    if dry_run {
+        print_would_write(out_path, "rust data array", data.len());
+        return Ok(());
+    }
+
+    let mut out_file = File::create(out_path)
+        .map_err(|e| WavGenError::CreateError(out_path.to_path_buf(), e.into()))?;
+    write_rust(
+        &data,
+        &ArrayOptions {
+            name: &convert_options.name,
+            constants: convert_options.constants,
+            columns: convert_options.columns,
+            const_array: convert_options.const_array,
+            split: false,
+            sample_coding: SampleCoding::Signed,
+        },
+        header.sampling_rate,
+        header.channel_count as u8,
+        out_path,
+        &mut out_file,
+    )?;
+
+    print_finished(&[out_path.to_path_buf()]);
+    Ok(())
+}
+
+/// Writes the `data` as a wav file at `out_path` with samples of `bit_depth` bits. If `rifx` is
+/// set the file is written in the big-endian RIFX format. If `rf64` is set, or the samples are
+/// too large for the 32 bit sizes of a wav file, the file is written in the RF64 format.
+/// If `out_path` is `-` the wav file is written to stdout.
+fn write_wav(
+    data: &[i16],
+    bit_depth: BitDepth,
+    number_channels: u8,
+    sampling_rate: u32,
+    rifx: bool,
+    rf64: bool,
+    out_path: &Path,
+) -> Result<(), WavGenError> {
+    let out_header = Header::new(
+        bit_depth.audio_format(),
+        number_channels as u16,
+        sampling_rate,
+        bit_depth.bits(),
+    );
+    let track = quantize(data, bit_depth);
+
+    // The RIFF chunk holds "WAVE", the fmt chunk and the data chunk
+    let riff_size = 4 + 24 + 8 + data.len() as u64 * (bit_depth.bits() / 8) as u64;
+    let rf64 = rf64 || (!rifx && riff_size > u32::MAX as u64);
+
+    if is_stdout(out_path) {
+        // The wav crate needs to seek, which stdout cannot do, so the file is written to memory first
+        let mut buffer = Cursor::new(Vec::<u8>::new());
+        write_track(out_header, &track, rifx, rf64, &mut buffer)
+            .and_then(|_| std::io::stdout().lock().write_all(buffer.get_ref()))
+            .map_err(|e| WavGenError::WriteError(PathBuf::from("stdout"), e.into()))
+    } else {
+        let out_file = File::create(out_path)
+            .map_err(|e| WavGenError::CreateError(out_path.to_path_buf(), e.into()))?;
+        write_track(
+            out_header,
+            &track,
+            rifx,
+            rf64,
+            &mut BufWriter::new(out_file),
+        )
+        .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))
+    }
+}
+
+/// The number of samples (of all the channels) that are quantized and written at a time when a wave
+/// is written while it is generated
+const STREAM_CHUNK_SAMPLES: usize = 65_536;
+
+/// The bit depth of the wav file if the wave can be written while it is generated. This is only
+/// possible if a single wav file is written and none of the options that work on the whole of the
+/// wave, such as --reverse or --normalize, are given.
+fn stream_bit_depth(cli: &Cli) -> Option<BitDepth> {
+    let wav_options = match &cli.command {
+        OutputTypeCommands::Wav(wav_options) => wav_options,
+        _ => return None,
+    };
+
+    let single_file = wav_options.bit_depths.is_empty()
+        && wav_options.also_rust.is_none()
+        && !wav_options.rifx
+        && !wav_options.rf64;
+    let whole_wave = cli.reverse
+        || cli.zero_cross
+        || cli.amplitude_jitter.is_some()
+        || cli.fade_in > 0
+        || cli.fade_out > 0
+        || cli.attack.is_some()
+        || cli.decay.is_some()
+        || cli.sustain.is_some()
+        || cli.release.is_some()
+        || cli.decay_tau.is_some()
+        || cli.tremolo_rate.is_some()
+        || cli.pan.is_some()
+        || cli.rng_state_out.is_some()
+        || cli.preemphasis.is_some()
+        || cli.preview
+        || cli.lufs
+        || cli.fail_on_silence.is_some()
+        || cli.comb_delay.is_some()
+        || cli.normalize
+        || cli.dc_offset.is_some()
+        || cli.limiter
+        || cli.window != Window::None
+        || cli.lead_in > 0
+        || cli.lead_out > 0
+        || cli.samples_only
+        || cli.dry_run
+        || cli.band_split
+        || cli.loop_period
+        || wav_options.dither
+        || !cli.channel_delays.is_empty();
+
+    match wav_options.format {
+        _ if !single_file || whole_wave => None,
+        SampleFormat::Int => Some(wav_options.bit_depth),
+        SampleFormat::Float => Some(BitDepth::Float),
+    }
+}
+
+/// True if `number_samples` for each of the channels fit into a wav file, i.e. the file does not
+/// need to be written in the RF64 format
+fn fits_wav_file(number_samples: u32, number_channels: u8, bit_depth: BitDepth) -> bool {
+    let data_size = number_samples as u64 * number_channels as u64 * (bit_depth.bits() / 8) as u64;
+    4 + 24 + 8 + data_size + data_size % 2 <= u32::MAX as u64
+}
+
+/// The values from -1.0 to 1.0 of one channel of the waves that can be written while they are
+/// generated, or `None` if the wave needs to be generated as a whole
+fn streamed_values(
+    gen_command: &GenCommands,
+    sampling_rate: u32,
+) -> Result<Option<Box<dyn Iterator<Item = f32>>>, WavGenError> {
+    let values: Box<dyn Iterator<Item = f32>> = match gen_command {
+        GenCommands::Sine {
+            frequency,
+            phase,
+            frequency_right: None,
+            note,
+            vibrato_rate: None,
+            ..
+        } => {
+            let frequency = note.unwrap_or(*frequency as f32);
+            check_nyquist(frequency, sampling_rate)?;
+            Box::new(sine_values(frequency, phase.to_radians(), sampling_rate))
+        }
+        GenCommands::Square {
+            frequency,
+            duty_cycle: duty,
+        }
+        | GenCommands::Pulse {
+            frequency,
+            duty,
+            pwm_rate: None,
+            ..
+        } => Box::new(square_values(*frequency as f32, *duty, sampling_rate)),
+        GenCommands::Triangle { frequency } => {
+            Box::new(triangle_values(*frequency as f32, sampling_rate))
+        }
+        GenCommands::Sawtooth {
+            frequency,
+            descending,
+            band_limited: false,
+        } => Box::new(sawtooth_values(
+            *frequency as f32,
+            *descending,
+            sampling_rate,
+        )),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(values))
+}
+
+/// Writes `number_samples` of the `values` scaled by the `volume` to each of the channels of a wav
+/// file at `out_path` with samples of `bit_depth` bits. The samples are quantized and written in
+/// chunks as they are generated so that the wave is never held in memory. Float samples are
+/// scaled directly from the values so they are not limited to 16 bits. If `out_path` is `-` the
+/// wav file is written to stdout. Returns the statistics of the written samples.
+#[allow(clippy::too_many_arguments)]
+fn write_wav_stream(
+    values: Box<dyn Iterator<Item = f32>>,
+    volume: u16,
+    number_samples: u32,
+    number_channels: u8,
+    bit_depth: BitDepth,
+    sampling_rate: u32,
+    out_path: &Path,
+    progress: &mut dyn FnMut(u32),
+) -> Result<SampleStats, WavGenError> {
+    let header = Header::new(
+        bit_depth.audio_format(),
+        number_channels as u16,
+        sampling_rate,
+        bit_depth.bits(),
+    );
+    let data_size = number_samples * number_channels as u32 * (bit_depth.bits() / 8) as u32;
+
+    // Unlike the wav crate no seeking is needed, so stdout is written to directly
+    let (mut writer, error_path): (Box<dyn Write>, PathBuf) = if is_stdout(out_path) {
+        (
+            Box::new(BufWriter::new(std::io::stdout().lock())),
+            PathBuf::from("stdout"),
+        )
+    } else {
+        let out_file = File::create(out_path)
+            .map_err(|e| WavGenError::CreateError(out_path.to_path_buf(), e.into()))?;
+        (Box::new(BufWriter::new(out_file)), out_path.to_path_buf())
+    };
+    let write_error = |e: std::io::Error| WavGenError::WriteError(error_path.clone(), e.into());
+
+    write_wav_header(header, data_size, &mut writer).map_err(write_error)?;
+
+    // Each sample is written to all the channels
+    let mut values = values
+        .take(number_samples as usize)
+        .flat_map(|value| std::iter::repeat_n(value, number_channels as usize));
+    let mut value_chunk = Vec::<f32>::with_capacity(STREAM_CHUNK_SAMPLES);
+    let mut chunk = Vec::<i16>::with_capacity(STREAM_CHUNK_SAMPLES);
+    let mut samples_done = 0;
+    let mut stats = SampleStats::default();
+    loop {
+        value_chunk.clear();
+        value_chunk.extend(values.by_ref().take(STREAM_CHUNK_SAMPLES));
+        if value_chunk.is_empty() {
+            break;
+        }
+        chunk.clear();
+        chunk.extend(value_chunk.iter().map(|value| to_sample(*value, volume)));
+        stats.add(&chunk);
+
+        let track = match bit_depth {
+            BitDepth::Float => wav::BitDepth::ThirtyTwoFloat(
+                value_chunk
+                    .iter()
+                    .map(|value| to_float_sample(*value, volume))
+                    .collect(),
+            ),
+            _ => quantize(&chunk, bit_depth),
+        };
+        write_samples_le(&track, &mut writer).map_err(write_error)?;
+
+        samples_done += chunk.len();
+        progress((samples_done / number_channels as usize) as u32);
+    }
+
+    // Chunks are padded to an even length
+    if data_size % 2 == 1 {
+        writer.write_all(&[0]).map_err(write_error)?;
+    }
+    writer.flush().map_err(write_error)?;
+
+    Ok(stats)
+}
+
+/// Writes the `track` with its `header` to the `writer` as a RIFX, RF64 or wav file
+fn write_track<W: Write + Seek>(
+    header: Header,
+    track: &wav::BitDepth,
+    rifx: bool,
+    rf64: bool,
+    writer: &mut W,
+) -> std::io::Result<()> {
+    if rifx {
+        write_rifx(header, track, writer)
+    } else if rf64 {
+        write_rf64(header, track, writer)
+    } else {
+        wav::write(header, track, writer)?;
+        writer.flush()
+    }
+}
+
+/// True if the output file name `-` has been given to write the output to stdout
+fn is_stdout(out_path: &Path) -> bool {
+    out_path == Path::new("-")
+}
+
+/// Converts the 16 bit samples in `data` to samples with `bit_depth` bits
+fn quantize(data: &[i16], bit_depth: BitDepth) -> wav::BitDepth {
+    match bit_depth {
+        // 8 bit wav samples are unsigned and centred on 128
+        BitDepth::Eight => wav::BitDepth::Eight(
+            data.iter()
+                .map(|s| (round_to_bits(*s as f32, 8) + 128) as u8)
+                .collect(),
+        ),
+        BitDepth::Sixteen => wav::BitDepth::Sixteen(data.to_vec()),
+        // The wav crate expects 24 bit samples in the upper three bytes of an i32
+        BitDepth::TwentyFour => {
+            wav::BitDepth::TwentyFour(data.iter().map(|s| (*s as i32) << 16).collect())
+        }
+        // Scaled in the same way as the float samples generated by `to_float_sample`
+        BitDepth::Float => {
+            wav::BitDepth::ThirtyTwoFloat(data.iter().map(|s| *s as f32 / FLOAT_SCALE).collect())
+        }
+    }
+}
+
+/// Rounds the `value`, in units of 16 bit samples, to the nearest sample with `bits` bits and clamps
+/// it to the range of these samples. All the conversions to fewer bits and from floating point samples
+/// use this so that they round in the same way.
+fn round_to_bits(value: f32, bits: u16) -> i32 {
+    let max = (1 << (bits - 1)) as f32;
+    (value * 2f32.powi(bits as i32 - 16))
+        .round()
+        .clamp(-max, max - 1.) as i32
+}
+
+/// The `data` with dither for reducing it to `bit_depth` if `dither` is set and the bit depth
+/// has fewer than 16 bits, otherwise the `data` itself
+fn dithered<'a>(
+    data: &'a [i16],
+    bit_depth: BitDepth,
+    dither: bool,
+    rng: &mut Rng,
+) -> Cow<'a, [i16]> {
+    if !dither || bit_depth.bits() >= 16 {
+        return Cow::Borrowed(data);
+    }
+
+    let mut dithered = data.to_vec();
+    apply_dither(&mut dithered, bit_depth.bits(), rng);
+    Cow::Owned(dithered)
+}
+
+/// Adds triangular probability density function (TPDF) dither of up to plus or minus one least
+/// significant bit of `bits` bit samples and rounds the samples to `bits` bits. Reducing the samples
+/// to `bits` bits, as `quantize` does, is then exact.
+fn apply_dither(data: &mut [i16], bits: u16, rng: &mut Rng) {
+    let lsb = (1 << (16 - bits)) as f32;
+
+    for sample in data.iter_mut() {
+        // The difference of two uniformly distributed numbers has a triangular distribution
+        let noise = (rng.gen::<f32>() - rng.gen::<f32>()) * lsb;
+        *sample = (round_to_bits(*sample as f32 + noise, bits) << (16 - bits)) as i16;
+    }
+}
+
+/// Converts the samples read from a wav file to 16 bit samples. This is the inverse of `quantize`.
+/// Returns `None` if there are no samples.
+fn dequantize(track: wav::BitDepth) -> Option<Vec<i16>> {
+    match track {
+        wav::BitDepth::Eight(samples) => {
+            Some(samples.into_iter().map(|s| (s as i16 - 128) << 8).collect())
+        }
+        wav::BitDepth::Sixteen(samples) => Some(samples),
+        wav::BitDepth::TwentyFour(samples) => {
+            Some(samples.into_iter().map(|s| (s >> 16) as i16).collect())
+        }
+        wav::BitDepth::ThirtyTwoFloat(samples) => Some(
+            samples
+                .into_iter()
+                .map(|s| round_to_bits(s * FLOAT_SCALE, 16) as i16)
+                .collect(),
+        ),
+        wav::BitDepth::Empty => None,
+    }
+}
+
+/// Writes each sample in `data` to stdout as a decimal number on its own line
+fn write_samples(data: &[i16]) -> Result<(), WavGenError> {
+    let stdout = std::io::stdout();
+    let mut buf_writer = BufWriter::new(stdout.lock());
+
+    for sample in data {
+        writeln!(buf_writer, "{}", sample)
+            .map_err(|e| WavGenError::WriteError(PathBuf::from("stdout"), e.into()))?;
+    }
+    buf_writer
+        .flush()
+        .map_err(|e| WavGenError::WriteError(PathBuf::from("stdout"), e.into()))
+}
+
+/// The file that the `band` is written to, with the centre frequency appended to the name of
+/// the output file
+fn band_file_path(out_path: &Path, band: &OctaveBand) -> PathBuf {
+    suffixed_file_path(out_path, &format!("{}hz", band.centre.round()))
+}
+
+/// Derives a file name by appending `suffix` to the name of the output file,
+/// e.g. for the suffix `125hz` the file `sine.wav` becomes `sine_125hz.wav`
+fn suffixed_file_path(out_path: &Path, suffix: &str) -> PathBuf {
+    let stem = out_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let file_name = match out_path.extension() {
+        Some(extension) => format!("{}_{}.{}", stem, suffix, extension.to_string_lossy()),
+        None => format!("{}_{}", stem, suffix),
+    };
+
+    out_path.with_file_name(file_name)
+}
+
+/// How the data is written as an array in the rust and C output
+struct ArrayOptions<'a> {
+    /// Name of the array
+    name: &'a str,
+    /// Write the constants SAMPLE_RATE and CHANNELS without the name, for rust arrays instead of
+    /// NAME_SAMPLE_RATE and NAME_CHANNELS
+    constants: bool,
+    /// Number of samples on each line
+    columns: u32,
+    /// Write a rust `const` instead of a `static`
+    const_array: bool,
+    /// Write each channel of stereo data to its own rust array
+    split: bool,
+    /// How the samples are written
+    sample_coding: SampleCoding,
+}
+
+/// How the samples are written in the rust, C, JSON and raw output
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum SampleCoding {
+    /// Signed 16 bit samples
+    Signed,
+    /// Unsigned samples of this many bits (see `unsigned_sample`)
+    Unsigned(u8),
+    /// 8 bit samples companded with G.711
+    Companded(Encoding),
+}
+
+impl SampleCoding {
+    /// The value that is written for the `sample`
+    fn value(&self, sample: i16) -> i32 {
+        match self {
+            SampleCoding::Signed => sample as i32,
+            SampleCoding::Unsigned(bits) => unsigned_sample(sample, *bits) as i32,
+            SampleCoding::Companded(Encoding::Mulaw) => linear_to_mulaw(sample) as i32,
+            SampleCoding::Companded(Encoding::Alaw) => linear_to_alaw(sample) as i32,
+        }
+    }
+
+    /// The type of the values in a rust array
+    fn rust_type(&self) -> &'static str {
+        match self {
+            SampleCoding::Signed => "i16",
+            SampleCoding::Unsigned(_) => "u16",
+            SampleCoding::Companded(_) => "u8",
+        }
+    }
+
+    /// The type of the values in a C array
+    fn c_type(&self) -> &'static str {
+        match self {
+            SampleCoding::Signed => "int16_t",
+            SampleCoding::Unsigned(_) => "uint16_t",
+            SampleCoding::Companded(_) => "uint8_t",
+        }
+    }
+}
+
+/// The coding of the samples given by the `--unsigned`, `--dac-bits` and `--encoding` options
+fn sample_coding(unsigned: bool, dac_bits: Option<u8>, encoding: Option<Encoding>) -> SampleCoding {
+    match (encoding, dac_bits) {
+        (Some(encoding), _) => SampleCoding::Companded(encoding),
+        (None, Some(bits)) => SampleCoding::Unsigned(bits),
+        (None, None) if unsigned => SampleCoding::Unsigned(16),
+        (None, None) => SampleCoding::Signed,
+    }
+}
+
+/// Rounds the `sample` to `bits` bits and converts it to an unsigned sample centred at midscale,
+/// e.g. 0 to 4095 around 2048 for 12 bits
+fn unsigned_sample(sample: i16, bits: u8) -> u16 {
+    (round_to_bits(sample as f32, bits as u16) + (1 << (bits - 1))) as u16
+}
+
+impl<'a> From<&'a RustOptions> for ArrayOptions<'a> {
+    fn from(rust_options: &'a RustOptions) -> Self {
+        ArrayOptions {
+            name: &rust_options.name,
+            constants: rust_options.constants,
+            columns: rust_options.columns,
+            const_array: rust_options.const_array,
+            split: rust_options.split,
+            sample_coding: sample_coding(
+                rust_options.unsigned,
+                rust_options.dac_bits,
+                rust_options.encoding,
+            ),
+        }
+    }
+}
+
+/// Writes the `data` as a rust data structure with the name in the `array_options` together with the
+/// `sampling_rate` and `number_channels` as the constants `<name>_SAMPLE_RATE` and `<name>_CHANNELS`. If
+/// `constants` is set in the `array_options` then these are written as the constants `SAMPLE_RATE`
+/// and `CHANNELS` instead. The data is a `static` unless `const_array` is set in the `array_options`. If `split`
+/// is set in the `array_options` the stereo `data` is written as the arrays `<name>_LEFT` and `<name>_RIGHT`.
+/// The type of the arrays is given by the `sample_coding` in the `array_options`.
+fn write_rust(
+    data: &[i16],
+    array_options: &ArrayOptions,
+    sampling_rate: u32,
+    number_channels: u8,
+    out_path: &Path,
+    out_file: &mut File,
+) -> Result<(), WavGenError> {
+    let mut buf_writer = BufWriter::new(out_file);
+
+    // The constants are only written once, either with or without the name
+    let prefix = if array_options.constants {
+        String::new()
+    } else {
+        format!("{}_", array_options.name)
+    };
+    writeln!(
+        buf_writer,
+        "pub const {}SAMPLE_RATE: u32 = {};",
+        prefix, sampling_rate
+    )
+    .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+    writeln!(
+        buf_writer,
+        "pub const {}CHANNELS: u8 = {};",
+        prefix, number_channels
+    )
+    .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+    writeln!(buf_writer).map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+
+    if !array_options.split {
+        return write_rust_array(
+            &mut buf_writer,
+            data,
+            array_options.name,
+            array_options,
+            out_path,
+        );
+    }
+
+    // Each channel has its own array
+    let left: Vec<i16> = data.iter().step_by(2).copied().collect();
+    let right: Vec<i16> = data.iter().skip(1).step_by(2).copied().collect();
+    write_rust_array(
+        &mut buf_writer,
+        &left,
+        &format!("{}_LEFT", array_options.name),
+        array_options,
+        out_path,
+    )?;
+    writeln!(buf_writer).map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+    write_rust_array(
+        &mut buf_writer,
+        &right,
+        &format!("{}_RIGHT", array_options.name),
+        array_options,
+        out_path,
+    )
+}
+
+/// Writes the `samples` as the rust array `name` formatted as given in the `array_options`
+fn write_rust_array<W: Write>(
+    buf_writer: &mut W,
+    samples: &[i16],
+    name: &str,
+    array_options: &ArrayOptions,
+    out_path: &Path,
+) -> Result<(), WavGenError> {
+    // A const can be inlined by the compiler while a static has a fixed address
+    let item = if array_options.const_array {
+        "const"
+    } else {
+        "static"
+    };
+    writeln!(
+        buf_writer,
+        "pub {} {}: [{}; {}] = [",
+        item,
+        name,
+        array_options.sample_coding.rust_type(),
+        samples.len()
+    )
+    .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+
+    write_array_body(buf_writer, samples, array_options, out_path)?;
+    writeln!(buf_writer, "];")
+        .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+
+    Ok(())
+}
+
+/// Writes the `samples` of a rust or C array, formatted as given in the `array_options`, with
+/// `columns` samples on each line
+fn write_array_body<W: Write>(
+    buf_writer: &mut W,
+    samples: &[i16],
+    array_options: &ArrayOptions,
+    out_path: &Path,
+) -> Result<(), WavGenError> {
+    let mut block_count = 0;
+    for sample in samples {
+        if block_count == 0 {
+            write!(buf_writer, "    ")
+                .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+        }
+        write!(
+            buf_writer,
+            "{:6},",
+            array_options.sample_coding.value(*sample)
+        )
+        .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+        block_count += 1;
+        if block_count == array_options.columns {
+            writeln!(buf_writer)
+                .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+            block_count = 0;
+        }
+    }
+
+    // Finish the last line unless it is already complete
+    if block_count != 0 {
+        writeln!(buf_writer)
+            .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+    }
+
+    Ok(())
+}
+
+/// Writes the `data` as a C header file with an `int16_t` array (or the type of the `sample_coding`) with the name in the `array_options` and
+/// its length as the define `<name>_LEN`. If `constants` is set then the `sampling_rate` and `number_channels` are also
+/// written as the defines `SAMPLE_RATE` and `CHANNELS`. The header is guarded by the define `<name>_H` and
+/// the array is `static const` so the header can be included in more than one source file.
+fn write_c_header(
+    data: &[i16],
+    array_options: &ArrayOptions,
+    sampling_rate: u32,
+    number_channels: u8,
+    out_path: &Path,
+    out_file: &mut File,
+) -> Result<(), WavGenError> {
+    let mut buf_writer = BufWriter::new(out_file);
+
+    writeln!(buf_writer, "#ifndef {}_H", array_options.name)
+        .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+    writeln!(buf_writer, "#define {}_H", array_options.name)
+        .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+    writeln!(buf_writer).map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+    writeln!(buf_writer, "#include <stdint.h>")
+        .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+    writeln!(buf_writer).map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+
+    if array_options.constants {
+        writeln!(buf_writer, "#define SAMPLE_RATE {}", sampling_rate)
+            .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+        writeln!(buf_writer, "#define CHANNELS {}", number_channels)
+            .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+    }
+    writeln!(
+        buf_writer,
+        "#define {}_LEN {}",
+        array_options.name,
+        data.len()
+    )
+    .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+    writeln!(buf_writer).map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+
+    writeln!(
+        buf_writer,
+        "static const {} {}[{}_LEN] = {{",
+        array_options.sample_coding.c_type(),
+        array_options.name,
+        array_options.name
+    )
+    .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+
+    write_array_body(&mut buf_writer, data, array_options, out_path)?;
+    writeln!(buf_writer, "}};")
+        .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+    writeln!(buf_writer).map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+    writeln!(buf_writer, "#endif")
+        .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+
+    Ok(())
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
-enum OutputType {
-    Wav,
-    Rust,
+/// Writes the `data` as a JSON document together with the `sampling_rate`, `number_channels`
+/// and the command line used to generate it, e.g.
+/// `{"sample_rate":44100,"channels":2,"command":"wav-gen json sine","samples":[0,0,61,61]}`.
+/// The samples are written as given by the `sample_coding`.
+fn write_json(
+    data: &[i16],
+    sampling_rate: u32,
+    number_channels: u8,
+    sample_coding: SampleCoding,
+    out_path: &Path,
+    out_file: File,
+) -> Result<(), WavGenError> {
+    let command = std::env::args().collect::<Vec<String>>().join(" ");
+    let samples: Vec<i32> = data.iter().map(|s| sample_coding.value(*s)).collect();
+    let document = serde_json::json!({
+        "sample_rate": sampling_rate,
+        "channels": number_channels,
+        "command": command,
+        "samples": samples,
+    });
+
+    let mut buf_writer = BufWriter::new(out_file);
+    serde_json::to_writer(&mut buf_writer, &document)
+        .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+    writeln!(buf_writer).map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))
 }
 
-enum GeneratedSize {
-    NumberSamples(u32),
-    Cyclic,
+/// Writes the `data` as raw 16 bit samples with the byte order `endian` and no header. The samples
+/// are written as given by the `sample_coding`, companded samples as a single byte.
+fn write_raw(
+    data: &[i16],
+    endian: Endian,
+    sample_coding: SampleCoding,
+    out_path: &Path,
+    out_file: File,
+) -> Result<(), WavGenError> {
+    let mut buf_writer = BufWriter::new(out_file);
+
+    for sample in data {
+        let value = sample_coding.value(*sample) as u16;
+        let bytes = match (sample_coding, endian) {
+            (SampleCoding::Companded(_), _) => &value.to_le_bytes()[..1],
+            (_, Endian::Big) => &value.to_be_bytes()[..],
+            (_, Endian::Little) => &value.to_le_bytes()[..],
+        };
+        buf_writer
+            .write_all(bytes)
+            .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))?;
+    }
+
+    buf_writer
+        .flush()
+        .map_err(|e| WavGenError::WriteError(out_path.to_path_buf(), e.into()))
 }
 
-/// Represents an harmonic as a frequency and it's relative amplitude to other harmonics
-#[derive(Debug)]
-struct Harmonic {
-    frequency: u32, // In hertz
-    amplitude: f32,
+/// Applies a gain that ramps linearly in decibels from `start_db` on the first frame to `end_db`
+/// on the last frame. All the channels of a frame have the same gain.
+fn apply_gain_ramp(data: &mut [i16], number_channels: u8, start_db: f32, end_db: f32) {
+    let number_frames = data.len() / number_channels as usize;
+    let db_increment = if number_frames > 1 {
+        (end_db - start_db) / (number_frames - 1) as f32
+    } else {
+        0.
+    };
+
+    for (frame_index, frame) in data.chunks_mut(number_channels as usize).enumerate() {
+        let gain = 10f32.powf((start_db + db_increment * frame_index as f32) / 20.);
+        for sample in frame {
+            *sample = (*sample as f32 * gain) as i16;
+        }
+    }
 }
 
-/// Generate wav files from the command line arguments provided.
-fn main() -> Result<(), WavGenError> {
-    let cli = Cli::parse();
+/// Returns the wave in `data` with `lead_in_frames` frames of silence before it and `lead_out_frames` frames
+/// of silence after it
+fn pad_with_silence(
+    data: &[i16],
+    number_channels: u8,
+    lead_in_frames: u32,
+    lead_out_frames: u32,
+) -> Vec<i16> {
+    let lead_in = lead_in_frames as usize * number_channels as usize;
+    let lead_out = lead_out_frames as usize * number_channels as usize;
 
-    let sampling_rate = 44100; // DEFAULT
-                               //let number_channels = 2; // DEFAULT
+    let mut padded = vec![0; lead_in + data.len() + lead_out];
+    padded[lead_in..lead_in + data.len()].copy_from_slice(data);
+    padded
+}
 
-    // Process output type command options
-    let (size, number_channels) = match cli.command {
-        OutputTypeCommands::Wav(ref wav_options) => (
-            GeneratedSize::NumberSamples(wav_options.duration * sampling_rate),
-            2,
-        ),
-        OutputTypeCommands::Rust(ref rust_options) => {
-            // If stereo need a even length so that left and right samples are present
-            if !rust_options.mono && rust_options.length % 2 != 0 {
-                let mut cmd = Cli::command();
-                cmd.error(
-                    ErrorKind::InvalidValue,
-                    "With stereo the length of the data structure needs to be an even number",
-                )
-                .exit();
-            }
-            let n_channels: u8 = if rust_options.mono { 1 } else { 2 };
-            let size = if !rust_options.cycle {
-                GeneratedSize::NumberSamples(rust_options.length / n_channels as u32)
-            } else {
-                GeneratedSize::Cyclic
-            };
+/// Multiplies the wave by the `window`. The window spans all the frames of the wave and
+/// all the channels of a frame have the same gain.
+fn apply_window(data: &mut [i16], number_channels: u8, window: Window) {
+    let (a0, a1) = match window {
+        Window::Hann => (0.5, 0.5),
+        Window::Hamming => (0.54, 0.46),
+        Window::None => return,
+    };
 
-            (size, n_channels)
+    let number_frames = data.len() / number_channels as usize;
+    if number_frames < 2 {
+        return;
+    }
+
+    for (frame_index, frame) in data.chunks_mut(number_channels as usize).enumerate() {
+        let x = frame_index as f32 / (number_frames - 1) as f32;
+        let gain = a0 - a1 * (2. * PI * x).cos();
+        for sample in frame.iter_mut() {
+            *sample = (*sample as f32 * gain).round() as i16;
         }
-    };
+    }
+}
 
-    let gen_command = match cli.command {
-        OutputTypeCommands::Wav(ref wav_options) => &wav_options.gen_command,
-        OutputTypeCommands::Rust(ref rust_options) => &rust_options.gen_command,
-    };
+/// Ramps the gain linearly from 0 to 1 over the first `fade_in_frames` frames and from 1 to 0 over the
+/// last `fade_out_frames` frames. All the channels of a frame have the same gain. The fades are
+/// limited to the length of the wave.
+fn apply_fade(data: &mut [i16], number_channels: u8, fade_in_frames: u32, fade_out_frames: u32) {
+    let number_frames = data.len() / number_channels as usize;
+    let fade_in_frames = (fade_in_frames as usize).min(number_frames);
+    let fade_out_frames = (fade_out_frames as usize).min(number_frames);
 
-    let data = match gen_command {
-        GenCommands::Sine { frequency } => {
-            let n_samples = match size {
-                GeneratedSize::Cyclic => sampling_rate * number_channels as u32 / frequency,
-                GeneratedSize::NumberSamples(number_samples) => number_samples,
-            };
-            gen_sine_wave(
-                *frequency,
-                n_samples,
-                number_channels,
-                cli.volume,
-                sampling_rate,
-            )
+    for (frame_index, frame) in data.chunks_mut(number_channels as usize).enumerate() {
+        let mut gain = 1.;
+        if frame_index < fade_in_frames {
+            gain *= frame_index as f32 / fade_in_frames as f32;
+        }
+        let frames_to_end = number_frames - 1 - frame_index;
+        if frames_to_end < fade_out_frames {
+            gain *= frames_to_end as f32 / fade_out_frames as f32;
         }
-        GenCommands::Sweep { start, finish } => {
-            let n_samples = match size {
-                GeneratedSize::Cyclic => {
-                    let mut cmd = Cli::command();
-                    cmd.error(
-                        ErrorKind::ArgumentConflict,
-                        "Specifying --cycle for the subcommand sweep is not meaningful",
-                    )
-                    .exit();
-                }
-                GeneratedSize::NumberSamples(n_samples) => n_samples,
-            };
 
-            gen_sweep_wave(
-                *start,
-                *finish,
-                n_samples,
-                number_channels,
-                cli.volume,
-                sampling_rate,
-            )
+        for sample in frame {
+            *sample = (*sample as f32 * gain) as i16;
         }
+    }
+}
 
-        GenCommands::Harmonics { infile } => {
-            let p = Path::new(infile);
-            let mut harmonics_set =
-                read_harmonics(p).map_err(|_| WavGenError::ReadError(p.to_path_buf()))?;
-            normalise_harmonics(&mut harmonics_set);
+/// Shapes the wave with an ADSR envelope. The gain rises linearly from 0 to 1 over the `attack_frames`,
+/// falls to the `sustain` level over the `decay_frames`, is held at the `sustain` level and falls to 0
+/// over the last `release_frames`. If the stages are longer than the wave they are clamped in the
+/// order attack, decay and release.
+fn apply_adsr(
+    data: &mut [i16],
+    number_channels: u8,
+    attack_frames: u32,
+    decay_frames: u32,
+    sustain: f32,
+    release_frames: u32,
+) {
+    let number_frames = data.len() / number_channels as usize;
+    let attack_frames = (attack_frames as usize).min(number_frames);
+    let decay_frames = (decay_frames as usize).min(number_frames - attack_frames);
+    let release_frames =
+        (release_frames as usize).min(number_frames - attack_frames - decay_frames);
+    let release_start = number_frames - release_frames;
 
-            let n_samples = match size {
-                GeneratedSize::Cyclic => {
-                    let frequencies = harmonics_set.iter().map(|h| h.frequency).collect();
-                    sync_period(&frequencies, sampling_rate)
-                }
-                GeneratedSize::NumberSamples(n_samples) => n_samples,
-            };
+    for (frame_index, frame) in data.chunks_mut(number_channels as usize).enumerate() {
+        let gain = if frame_index < attack_frames {
+            frame_index as f32 / attack_frames as f32
+        } else if frame_index < attack_frames + decay_frames {
+            let decayed = (frame_index - attack_frames) as f32 / decay_frames as f32;
+            1. - (1. - sustain) * decayed
+        } else if frame_index < release_start {
+            sustain
+        } else {
+            let frames_to_end = number_frames - 1 - frame_index;
+            sustain * frames_to_end as f32 / release_frames as f32
+        };
 
-            gen_harmonics(
-                &harmonics_set,
-                n_samples,
-                number_channels,
-                cli.volume,
-                sampling_rate,
-            )?
+        for sample in frame {
+            *sample = (*sample as f32 * gain) as i16;
         }
-    };
+    }
+}
 
-    let out_path = Path::new(&cli.out_file_name);
-    let mut out_file =
-        File::create(out_path).map_err(|_| WavGenError::CreateError(out_path.to_path_buf()))?;
+/// Multiplies each frame by `exp(-t / tau_frames)` where `t` is the frame number, so that the wave
+/// decays to 37% after `tau_frames` frames. All the channels of a frame have the same gain.
+fn apply_exp_decay(data: &mut [i16], number_channels: u8, tau_frames: f32) {
+    for (frame_index, frame) in data.chunks_mut(number_channels as usize).enumerate() {
+        let gain = (-(frame_index as f64) / tau_frames as f64).exp() as f32;
 
-    match cli.command {
-        OutputTypeCommands::Wav(_) => {
-            let out_header = Header::new(wav::header::WAV_FORMAT_PCM, 2, sampling_rate, 16);
-            wav::write(out_header, &wav::BitDepth::Sixteen(data), &mut out_file)
-                .map_err(|_| WavGenError::WriteError(out_path.to_path_buf()))?;
+        for sample in frame {
+            *sample = (*sample as f32 * gain) as i16;
         }
-        OutputTypeCommands::Rust(rust_options) => {
-            write_rust(&data, rust_options.name.as_str(), out_path, &mut out_file)?;
+    }
+}
+
+/// Modulates the amplitude of each frame by `1 - depth * (1 + sin(2π * rate * t / sampling_rate)) / 2`
+/// where `t` is the frame number, so the gain goes between 1 and `1 - depth`. The `depth` is clamped
+/// to 0.0 to 1.0.
+fn apply_tremolo(data: &mut [i16], number_channels: u8, rate: f32, depth: f32, sampling_rate: u32) {
+    let depth = depth.clamp(0., 1.);
+    for (frame_index, frame) in data.chunks_mut(number_channels as usize).enumerate() {
+        let radians = 2. * PI * rate * frame_index as f32 / sampling_rate as f32;
+        let gain = 1. - depth * (1. + radians.sin()) / 2.;
+
+        for sample in frame {
+            *sample = (*sample as f32 * gain) as i16;
         }
-    };
+    }
+}
 
-    bunt::println!(
-        "{$bold+green}Finished{/$} writing to {}",
-        out_path.display()
-    );
+/// Pans the interleaved stereo samples in `data` with a constant power pan law. With a `pan` of -1.0
+/// only the left channel is heard, with 1.0 only the right channel. In the centre both channels
+/// have a gain of -3dB.
+fn apply_pan(data: &mut [i16], pan: f32) {
+    let angle = (pan + 1.) * PI / 4.;
+    let (left_gain, right_gain) = (angle.cos(), angle.sin());
 
-    Ok(())
+    for frame in data.chunks_mut(2) {
+        frame[0] = (frame[0] as f32 * left_gain) as i16;
+        if let Some(right) = frame.get_mut(1) {
+            *right = (*right as f32 * right_gain) as i16;
+        }
+    }
 }
 
-/// Generate a sine wave as a set of `i16` samples and returns this.
-///
-/// # Arguments
-/// * `frequency`- The frequency of the sine wave in hertz
-/// * `number_samples` - the number of samples to be generated.
-///                      The duration of the generated wave is the `number_samples/sampling_rate`.
-/// * `number_channels` - The number of channels (1 or 2)
-/// * `volume`- The volume of the generated sine wave
-/// * `sampling_rate`- The rate at which the wave wave is sampled, e.g 44100 hertz.
-///                    The `sample_rate` and the `duration` determine the the size of `data`  
-fn gen_sine_wave(
-    frequency: u32,
-    number_samples: u32,
-    number_channels: u8,
-    volume: u16,
-    sampling_rate: u32,
-) -> Vec<i16> {
-    let mut data = Vec::<i16>::new();
+/// Soft clips the samples with `volume * tanh(sample / volume)`. Small samples are nearly unchanged
+/// while large samples approach `volume` without exceeding it. The `volume` is limited to the
+/// range of `i16`.
+fn apply_soft_clip(data: &mut [i16], volume: u16) {
+    let limit = volume.min(i16::MAX as u16) as f32;
+    if limit == 0. {
+        return;
+    }
 
-    for t in 0..number_samples {
-        let radians = (t as f32 * 2. * PI * frequency as f32) / sampling_rate as f32;
-        let amplitude = (radians.sin() * volume as f32) as i16;
+    for sample in data.iter_mut() {
+        *sample = (limit * (*sample as f32 / limit).tanh()).round() as i16;
+    }
+}
 
-        // Data consists  of left channel followed by right channel sample. As we are generating stereo
-        // with both left and right channel being the same, two identical samples are written each time.
-        data.push(amplitude);
-        if number_channels == 2 {
-            data.push(amplitude);
-        }
+/// Scales `data` with a single gain so that the largest magnitude of the samples is `volume`.
+/// A wave that is all zeros is left as it is.
+fn normalize_peak(data: &mut [i16], volume: u16) {
+    let peak = data
+        .iter()
+        .map(|sample| (*sample as i32).abs())
+        .max()
+        .unwrap_or_default();
+    if peak == 0 {
+        return;
     }
 
-    data
+    let gain = volume.min(i16::MAX as u16) as f32 / peak as f32;
+    for sample in data.iter_mut() {
+        *sample = (*sample as f32 * gain)
+            .round()
+            .clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+    }
 }
 
-/// Generate a sweeping sine wave as a set of `i16` samples and returns it
-///
-/// # Arguments
-/// * `start` - The start frequency of sweep in hertz
-/// * `finish`- The finishing frequency of the sweep in hertz
-/// * ´number_samples" - the number of samples to be generated.
-///                      The duration of the generated wave is the `number_samples/sampling_rate`.
-/// * `number_channels` - The number of channels (1 or 2)
-/// * `volume`- The volume of the generated wave
-/// * `sampling_rate`- The rate at which the wave wave is sampled, e.g 44100 hertz.
-///                    The `sample_rate` and the `duration` determine the the size of `data`  
-fn gen_sweep_wave(
-    start: u32,
-    finish: u32,
-    number_samples: u32,
-    number_channels: u8,
-    volume: u16,
-    sampling_rate: u32,
-) -> Vec<i16> {
-    let mut data = Vec::<i16>::new();
+/// The largest magnitude of a sample, as a fraction of the peak of the wave, that is taken to be at a zero crossing
+const ZERO_CROSSING_TOLERANCE: f32 = 0.05;
 
-    let frequency_increment: f32 = (finish as f32 - start as f32) / number_samples as f32;
-    let mut sweep_frequency: f32 = start as f32;
+/// Trims the end of `data` back to the last frame (apart from the first) where the samples of all the channels
+/// are near zero, i.e. within `ZERO_CROSSING_TOLERANCE` of the peak. If there is no such frame `data` is unchanged.
+fn trim_to_zero_crossing(data: &mut Vec<i16>, number_channels: u8) {
+    let number_channels = number_channels as usize;
+    let peak = data
+        .iter()
+        .map(|sample| (*sample as i32).abs())
+        .max()
+        .unwrap_or_default();
+    let tolerance = (peak as f32 * ZERO_CROSSING_TOLERANCE).max(1.);
 
-    for t in 0..number_samples {
-        let r = (t as f32 * 2. * PI * sweep_frequency) / sampling_rate as f32;
-        let amplitude = (r.sin() * volume as f32) as i16;
+    let last_crossing = (1..data.len() / number_channels).rev().find(|frame_index| {
+        data[frame_index * number_channels..(frame_index + 1) * number_channels]
+            .iter()
+            .all(|sample| (*sample as f32).abs() <= tolerance)
+    });
 
-        // Data consists  of left channel followed by right channel sample. As we are generating stereo
-        // with both left and right channel being the same, two identical samples are written each time.
-        data.push(amplitude);
-        if number_channels == 2 {
-            data.push(amplitude);
-        }
+    if let Some(frame_index) = last_crossing {
+        data.truncate((frame_index + 1) * number_channels);
+    }
+}
 
-        // Adjust the frequency for the next iteration
-        sweep_frequency += frequency_increment;
+/// Reverses the order of the frames in `data`. The order of the channels in each frame stays the same.
+fn reverse_frames(data: &mut [i16], number_channels: u8) {
+    data.reverse();
+    for frame in data.chunks_mut(number_channels as usize) {
+        frame.reverse();
     }
+}
 
-    data
+/// Adds `dc_offset` to every sample. Samples outside the range of `i16` are handled as given
+/// by `on_clip`.
+fn apply_dc_offset(data: &mut [i16], dc_offset: i16, on_clip: OnClip) -> Result<(), WavGenError> {
+    for (i, sample) in data.iter_mut().enumerate() {
+        *sample = on_clip.sample(*sample as i32 + dc_offset as i32, i)?;
+    }
+    Ok(())
 }
 
-#[allow(unused_variables)]
-fn gen_harmonics(
-    harmonics_set: &[Harmonic],
-    number_samples: u32,
-    number_channels: u8,
-    volume: u16,
-    sampling_rate: u32,
-) -> Result<Vec<i16>, WavGenError> {
-    // Generate a initial set of data
-    if let Some(h) = harmonics_set.first() {
-        let mut data = gen_sine_wave(
-            h.frequency,
-            number_samples,
-            number_channels,
-            (h.amplitude * volume as f32) as u16,
-            sampling_rate,
-        );
-        // Overlay the other harmonics
-        for harmonic_entry in harmonics_set.iter().skip(1) {
-            let overlay_data = gen_sine_wave(
-                harmonic_entry.frequency,
-                number_samples,
-                number_channels,
-                (harmonic_entry.amplitude * volume as f32) as u16,
-                sampling_rate,
-            );
+/// Multiplies each sample by a random gain that is uniformly distributed between `-jitter_db`
+/// and `jitter_db` decibels
+fn apply_amplitude_jitter(data: &mut [i16], jitter_db: f32, rng: &mut Rng) {
+    for sample in data.iter_mut() {
+        let gain = 10f32.powf(rng.next_bipolar() * jitter_db / 20.);
+        *sample = (*sample as f32 * gain) as i16;
+    }
+}
 
-            for i in 0..data.len() {
-                data[i] += overlay_data[i];
-            }
-        }
-        Ok(data)
+/// Joins a set of generated segments, one after the other, into one set of samples
+fn concat_segments(segments: &[Vec<i16>]) -> Vec<i16> {
+    segments.concat()
+}
+
+/// The range of the wavetable frame sizes. A frame needs at least 4 samples to hold a harmonic below
+/// the Nyquist frequency.
+const FRAME_SIZES: RangeInclusive<u32> = 4..=65536;
+
+/// Parses a wavetable frame size, which has to be a power of two in `FRAME_SIZES`
+fn parse_frame_size(s: &str) -> Result<u32, String> {
+    let n: u32 = s.parse().map_err(|_| format!("{} is not a number", s))?;
+    if !n.is_power_of_two() {
+        Err(format!("{} is not a power of two", n))
+    } else if !FRAME_SIZES.contains(&n) {
+        Err(format!(
+            "{} is not from {} to {}",
+            n,
+            FRAME_SIZES.start(),
+            FRAME_SIZES.end()
+        ))
     } else {
-        Err(WavGenError::NoHarmonics)
+        Ok(n)
     }
 }
 
-fn read_harmonics(harmonics_path: &Path) -> Result<Vec<Harmonic>, Box<dyn Error>> {
-    //fn read_harmonics(harmonics_path: &Path) -> Result<Vec<Harmonic>,  HarmonicReadError> {
+/// The keys of a DTMF keypad. The row of a key gives its low tone and the column its high tone.
+const DTMF_KEYS: [[char; 4]; 4] = [
+    ['1', '2', '3', 'A'],
+    ['4', '5', '6', 'B'],
+    ['7', '8', '9', 'C'],
+    ['*', '0', '#', 'D'],
+];
 
-    let mut rdr = csv::Reader::from_path(harmonics_path)?;
-    let mut harmonics = Vec::<Harmonic>::new();
+/// The frequencies in hertz of the DTMF rows
+const DTMF_ROW_FREQUENCIES: [f32; 4] = [697., 770., 852., 941.];
 
-    let mut line_number = 1;
+/// The frequencies in hertz of the DTMF columns
+const DTMF_COLUMN_FREQUENCIES: [f32; 4] = [1209., 1336., 1477., 1633.];
 
-    for result in rdr.records() {
-        let record = result.map_err(|_| WavGenError::ReadError(harmonics_path.to_path_buf()))?;
-
-        let f: u32 = record
-            .get(0)
-            .ok_or(WavGenError::HarmonicParseError(line_number))?
-            .trim()
-            .parse()
-            .map_err(|_| WavGenError::HarmonicParseError(line_number))?;
-
-        let a: f32 = record
-            .get(1)
-            .ok_or(WavGenError::HarmonicParseError(line_number))?
-            .trim()
-            .parse()
-            .map_err(|_| WavGenError::HarmonicParseError(line_number))?;
+/// Checks that `digit` is a key of a DTMF keypad. The letters can be lower case.
+fn parse_dtmf_digit(digit: &str) -> Result<char, String> {
+    let mut chars = digit.chars();
+    match (chars.next().map(|c| c.to_ascii_uppercase()), chars.next()) {
+        (Some(key), None) if DTMF_KEYS.iter().flatten().any(|k| *k == key) => Ok(key),
+        _ => Err(String::from(
+            "the key needs to be one of 0-9, *, #, A, B, C or D",
+        )),
+    }
+}
 
-        harmonics.push(Harmonic {
-            frequency: f,
-            amplitude: a,
-        });
+/// Returns the row (low) and column (high) frequencies in hertz of a DTMF `key`
+fn dtmf_frequencies(key: char) -> (f32, f32) {
+    for (row, keys) in DTMF_KEYS.iter().enumerate() {
+        if let Some(column) = keys.iter().position(|k| *k == key) {
+            return (DTMF_ROW_FREQUENCIES[row], DTMF_COLUMN_FREQUENCIES[column]);
+        }
+    }
+    unreachable!("the key has been checked by parse_dtmf_digit")
+}
+
+/// Converts a note name such as `A4`, `C#5` or `Bb3` to its frequency in hertz in equal
+/// temperament with A4 as 440 hertz. The octaves are 0 to 9.
+fn note_to_frequency(note: &str) -> Result<f32, String> {
+    let invalid = || format!("{} is not a note name, e.g. A4, C#5 or Bb3", note);
 
-        line_number += 1;
+    let mut chars = note.chars();
+    let semitone: i32 = match chars.next().map(|c| c.to_ascii_uppercase()) {
+        Some('C') => 0,
+        Some('D') => 2,
+        Some('E') => 4,
+        Some('F') => 5,
+        Some('G') => 7,
+        Some('A') => 9,
+        Some('B') => 11,
+        _ => return Err(invalid()),
+    };
+
+    let rest = chars.as_str();
+    let (accidental, octave) = if let Some(octave) = rest.strip_prefix('#') {
+        (1, octave)
+    } else if let Some(octave) = rest.strip_prefix('b') {
+        (-1, octave)
+    } else {
+        (0, rest)
+    };
+
+    // The octave is a single digit
+    let octave = match octave.chars().collect::<Vec<char>>()[..] {
+        [digit] => digit.to_digit(10).ok_or_else(invalid)? as i32,
+        _ => return Err(invalid()),
+    };
+
+    // The MIDI note number where A4 is 69
+    let midi_note = 12 * (octave + 1) + semitone + accidental;
+    Ok(440. * 2f32.powf((midi_note - 69) as f32 / 12.))
+}
+
+/// Parses a duration in seconds that cannot be negative
+fn parse_duration(s: &str) -> Result<f32, String> {
+    let duration: f32 = s.parse().map_err(|_| format!("{} is not a number", s))?;
+    if duration >= 0. && duration.is_finite() {
+        Ok(duration)
+    } else {
+        Err(format!("{} is not a valid duration", s))
     }
+}
 
-    Ok(harmonics)
+/// Parses a time constant in milliseconds, which needs to be larger than 0
+fn parse_time_constant(s: &str) -> Result<f32, String> {
+    let tau: f32 = s.parse().map_err(|_| format!("{} is not a number", s))?;
+    if tau > 0. && tau.is_finite() {
+        Ok(tau)
+    } else {
+        Err(format!("{} is not a valid time constant", s))
+    }
 }
 
-/// Normalise the amplitudes of the harmonics so that the sum of them all is 1
-fn normalise_harmonics(harmonics_set: &mut [Harmonic]) {
-    let mut sum = 0.;
-    for h in harmonics_set.iter_mut() {
-        sum += h.amplitude;
+/// Parses a tempo in beats per minute, which needs to be larger than 0
+fn parse_bpm(s: &str) -> Result<f32, String> {
+    let bpm: f32 = s.parse().map_err(|_| format!("{} is not a number", s))?;
+    if bpm > 0. && bpm.is_finite() {
+        Ok(bpm)
+    } else {
+        Err(format!("{} is not a valid tempo", s))
     }
+}
 
-    for h in harmonics_set.iter_mut() {
-        h.amplitude /= sum;
+/// Parses an amplitude as a fraction of full scale from 0.0 to 1.0
+fn parse_amplitude(s: &str) -> Result<f32, String> {
+    let amplitude: f32 = s.parse().map_err(|_| format!("{} is not a number", s))?;
+    if (0. ..=1.).contains(&amplitude) {
+        Ok(amplitude)
+    } else {
+        Err(format!("{} is not between 0.0 and 1.0", s))
     }
 }
 
-fn write_rust(
-    data: &Vec<i16>,
-    data_struct_name: &str,
-    out_path: &Path,
-    out_file: &mut File,
-) -> Result<(), WavGenError> {
-    let mut buf_writer = BufWriter::new(out_file);
+/// Checks that the `frequency` is above 0 hertz and below the Nyquist frequency (half the
+/// `sampling_rate`) as higher frequencies alias to a lower frequency
+fn check_nyquist(frequency: f32, sampling_rate: u32) -> Result<(), WavGenError> {
+    if frequency.is_nan() || frequency <= 0. {
+        return Err(WavGenError::NotPositiveFrequency(frequency));
+    }
+    if frequency >= sampling_rate as f32 / 2. {
+        return Err(WavGenError::AboveNyquist(frequency, sampling_rate));
+    }
+    Ok(())
+}
 
-    writeln!(
-        buf_writer,
-        "pub static {}: [i16; {}] = [",
-        data_struct_name,
-        data.len()
-    )
-    .map_err(|_| WavGenError::WriteError(out_path.to_path_buf()))?;
+/// Converts a time in milliseconds to the number of samples (per channel) at the sampling rate
+fn ms_to_samples(ms: u32, sampling_rate: u32) -> u32 {
+    (ms as u64 * sampling_rate as u64 / 1000) as u32
+}
 
-    let mut block_count = 0;
-    for sample in data {
-        if block_count == 0 {
-            write!(buf_writer, "    ")
-                .map_err(|_| WavGenError::WriteError(out_path.to_path_buf()))?;
+/// The number of samples to generate of a wave with the `frequencies` that is `number_samples` long.
+/// With `looped` this is the exact period of the wave, if it is shorter, so that the wave can be
+/// repeated with `repeat_frames`. Otherwise it is `number_samples`.
+fn loop_samples(looped: bool, frequencies: &[f32], number_samples: u32, sampling_rate: u32) -> u32 {
+    if !looped {
+        return number_samples;
+    }
+
+    let mut period: u64 = 1;
+    for frequency in frequencies {
+        if frequency.fract() != 0. {
+            let mut cmd = Cli::command();
+            cmd.error(
+                ErrorKind::InvalidValue,
+                format!(
+                    "--loop needs frequencies that are whole numbers of hertz, not {}Hz",
+                    frequency
+                ),
+            )
+            .exit();
         }
-        write!(buf_writer, "{:6},", sample)
-            .map_err(|_| WavGenError::WriteError(out_path.to_path_buf()))?;
-        block_count += 1;
-        if block_count == 10 {
-            writeln!(buf_writer).map_err(|_| WavGenError::WriteError(out_path.to_path_buf()))?;
-            block_count = 0;
+
+        // The wave does `frequency` cycles every `sampling_rate` samples, so it repeats after
+        // the samples where both are divided by their greatest common divisor
+        let rate = sampling_rate as u64;
+        period = lcm(period, rate / gcd(rate, *frequency as u64));
+        if period >= number_samples as u64 {
+            return number_samples;
         }
     }
 
-    writeln!(buf_writer).map_err(|_| WavGenError::WriteError(out_path.to_path_buf()))?;
-    writeln!(buf_writer, "];").map_err(|_| WavGenError::WriteError(out_path.to_path_buf()))?;
+    period as u32
+}
 
-    Ok(())
+/// Repeats the frames of `data` until there are `number_samples` frames
+fn repeat_frames(data: Vec<i16>, number_samples: u32, number_channels: u8) -> Vec<i16> {
+    let length = (number_samples * number_channels as u32) as usize;
+    if data.len() >= length || data.is_empty() {
+        return data;
+    }
+
+    data.iter().copied().cycle().take(length).collect()
 }
 
-/// Finds the least common numerator of the periods in a set of sine waves, i.e the time (in number of samples) at which
-/// all the sine wave start at zero (are synchronised) again.
+/// Finds the period of a set of sine waves, i.e the time (in number of samples) at which all the sine waves start at
+/// zero (are synchronised) again. The sum of the sine waves repeats with the greatest common divisor of the
+/// frequencies, so the period is the sampling rate divided by it, rounded up to a whole number of samples. This is
+/// at least one full cycle of the lowest frequency. A frequency of zero has no cycle and gives an error.
 #[allow(clippy::ptr_arg)]
-fn sync_period(frequencies: &Vec<u32>, sampling_rate: u32) -> u32 {
-    let scale: u32 = 20000; // Used to scale up each period so that it remains an integer
-    let scaled_sample_periods: Vec<u32> = frequencies
-        .iter()
-        .map(|f| sampling_rate * scale / f)
-        .collect();
+fn sync_period(frequencies: &Vec<u32>, sampling_rate: u32) -> Result<u32, WavGenError> {
+    if frequencies.contains(&0) {
+        return Err(WavGenError::ZeroFrequency);
+    }
+
+    let common_frequency = frequencies.iter().fold(0, |common, f| gcd(common, *f));
+    Ok(sampling_rate.div_ceil(common_frequency.max(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sync_period_of_coprime_frequencies() {
+        // 37 and 41 hertz both complete a whole number of cycles in one second
+        assert_eq!(sync_period(&vec![37, 41], 44100).unwrap(), 44100);
+    }
+
+    #[test]
+    fn sync_period_of_zero_frequency() {
+        assert!(matches!(
+            sync_period(&vec![440, 0], 44100),
+            Err(WavGenError::ZeroFrequency)
+        ));
+    }
+
+    #[test]
+    fn harmonic_of_zero_hertz_is_rejected() {
+        let harmonics =
+            read_harmonics("frequency,amplitude\n440,1\n0,0.5\n".as_bytes(), false).unwrap();
+        assert_eq!(harmonics[1].frequency, 0.);
+        assert!(matches!(
+            check_nyquist(harmonics[1].frequency, 44100),
+            Err(WavGenError::NotPositiveFrequency(_))
+        ));
+    }
+
+    #[test]
+    fn harmonics_with_comments_and_blank_lines() {
+        let csv = "frequency,amplitude,phase\n\
+                   # The fundamental\n\
+                   440,1\n\
+                   \n\
+                   # The odd harmonics\n\
+                   1320,0.33,180\n\
+                   2200,0.2\n";
+        let harmonics = read_harmonics(csv.as_bytes(), false).unwrap();
 
-    let mut period: u32 = 1;
-    for v in scaled_sample_periods.iter() {
-        period = lcm(period, *v);
+        let frequencies: Vec<f32> = harmonics.iter().map(|h| h.frequency).collect();
+        assert_eq!(frequencies, vec![440., 1320., 2200.]);
+        assert_eq!(harmonics[1].phase, 180.);
     }
 
-    period / scale
+    #[test]
+    fn harmonic_parse_error_reports_the_line_in_the_file() {
+        let csv = "frequency,amplitude\n# A comment\n440,1\nabc,1\n";
+        let error = read_harmonics(csv.as_bytes(), false).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 4: could not parse frequency \"abc\""
+        );
+    }
+
+    #[test]
+    fn tremolo_gain_is_between_one_and_one_minus_depth() {
+        // One cycle of the tremolo at 4 frames
+        let mut data = vec![10000; 4];
+        apply_tremolo(&mut data, 1, 1., 1., 4);
+        assert_eq!(data, vec![5000, 0, 5000, 10000]);
+
+        // The depth is clamped to 1.0
+        let mut data = vec![10000; 4];
+        apply_tremolo(&mut data, 1, 1., 2., 4);
+        assert_eq!(data, vec![5000, 0, 5000, 10000]);
+    }
+
+    #[test]
+    fn float_samples_round_trip() {
+        let data = vec![i16::MIN, -12345, -1, 0, 1, 12345, i16::MAX];
+        let track = quantize(&data, BitDepth::Float);
+        assert_eq!(dequantize(track).unwrap(), data);
+    }
+
+    #[test]
+    fn eight_bit_samples_are_rounded() {
+        let track = quantize(&[i16::MIN, -129, 127, 128, i16::MAX], BitDepth::Eight);
+        assert!(
+            matches!(track, wav::BitDepth::Eight(samples) if samples == vec![0, 127, 128, 129, 255])
+        );
+    }
+
+    #[test]
+    fn output_type_from_extension() {
+        assert!(detect_output_type("tone.wav") == Some(OutputType::Wav));
+        assert!(detect_output_type("TONE.WAV") == Some(OutputType::Wav));
+        assert!(detect_output_type("tone.rs") == Some(OutputType::Rust));
+        assert!(detect_output_type("tone.h") == Some(OutputType::C));
+        assert!(detect_output_type("tone.json") == Some(OutputType::Json));
+        assert!(detect_output_type("tone.raw") == Some(OutputType::Raw));
+        assert!(detect_output_type("-") == Some(OutputType::Wav));
+        assert!(detect_output_type("tone.mp3").is_none());
+        assert!(detect_output_type("tone").is_none());
+    }
+
+    #[test]
+    fn output_type_detected_for_wave_form() {
+        let cli =
+            parse_cli_from(["wav-gen", "sine", "tone.rs"].map(OsString::from).to_vec()).unwrap();
+        assert!(matches!(
+            cli.command,
+            OutputTypeCommands::Rust(RustOptions {
+                gen_command: GenCommands::Sine { .. },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn option_value_with_name_of_wave_form() {
+        let args = ["wav-gen", "--rng-state-out", "sine", "noise", "out.wav"];
+        let cli = parse_cli_from(args.map(OsString::from).to_vec()).unwrap();
+        assert_eq!(cli.rng_state_out.as_deref(), Some("sine"));
+        assert_eq!(cli.out_file_name, "out.wav");
+        assert!(matches!(
+            cli.command,
+            OutputTypeCommands::Wav(WavOptions {
+                gen_command: GenCommands::Noise,
+                ..
+            })
+        ));
+    }
 }
@@ -0,0 +1,80 @@
+//! ASCII art preview of the generated wave, like an oscilloscope
+use std::io::{stderr, IsTerminal};
+
+/// Number of columns in the preview
+const WIDTH: usize = 72;
+
+/// Number of rows in the preview. Odd so that there is a row for zero
+const HEIGHT: usize = 15;
+
+/// Length of the start of the wave shown in the preview in milliseconds
+const PREVIEW_MS: u32 = 10;
+
+/// Prints a preview of the start of the first channel to stderr. Nothing is printed
+/// if stderr is not a terminal, so that the preview does not end up in log files.
+pub fn print_preview(data: &[i16], number_channels: u8, sampling_rate: u32) {
+    if stderr().is_terminal() {
+        eprint!("{}", render_preview(data, number_channels, sampling_rate));
+    }
+}
+
+/// Renders the first `PREVIEW_MS` milliseconds of the first channel in `data`. The vertical
+/// scale is set by the peak of the samples shown.
+pub fn render_preview(data: &[i16], number_channels: u8, sampling_rate: u32) -> String {
+    let window = ((PREVIEW_MS as u64 * sampling_rate as u64 / 1000) as usize).max(1);
+    let samples: Vec<i16> = data
+        .iter()
+        .step_by(number_channels as usize)
+        .take(window)
+        .copied()
+        .collect();
+    if samples.is_empty() {
+        return String::new();
+    }
+
+    let peak = samples
+        .iter()
+        .map(|s| s.unsigned_abs())
+        .max()
+        .unwrap_or_default()
+        .max(1) as f32;
+    let row = |sample: i16| -> usize {
+        ((peak - sample as f32) / (2. * peak) * (HEIGHT - 1) as f32).round() as usize
+    };
+
+    let mut grid = vec![vec![' '; WIDTH]; HEIGHT];
+    for cell in grid[HEIGHT / 2].iter_mut() {
+        *cell = '-';
+    }
+
+    // Each column shows the range of the samples that fall into it
+    let columns = WIDTH.min(samples.len());
+    for column in 0..columns {
+        let start = column * samples.len() / columns;
+        let end = ((column + 1) * samples.len() / columns).max(start + 1);
+        let min = samples[start..end]
+            .iter()
+            .min()
+            .copied()
+            .unwrap_or_default();
+        let max = samples[start..end]
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or_default();
+        for line in grid.iter_mut().take(row(min) + 1).skip(row(max)) {
+            line[column] = '*';
+        }
+    }
+
+    let mut preview = format!(
+        "First {}ms of channel 0 (peak {}):\n",
+        PREVIEW_MS, peak as u16
+    );
+    for line in grid {
+        preview.push('|');
+        preview.extend(line);
+        preview.push('\n');
+    }
+    preview
+}
@@ -0,0 +1,102 @@
+//! A small seedable pseudo random number generator (xorshift64*).
+//!
+//! The generator implements `rand::RngCore` so it can be used with the distributions of the `rand` crate.
+//!
+//! The whole state of the generator is one `u64` so that it can be saved to a file
+//! and a later invocation can continue with the same sequence of random numbers.
+//!
+//! The state file contains the state as 16 hexadecimal digits followed by a newline, e.g.
+//!
+//! ```text
+//! 9e3779b97f4a7c15
+//! ```
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::RngCore;
+
+use crate::error::WavGenError;
+
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a generator from a seed. The same seed always gives the same sequence.
+    pub fn from_seed(seed: u64) -> Rng {
+        // Scramble the seed with splitmix64 so that similar seeds (and zero) give good states
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        Rng::from_state(z)
+    }
+
+    /// Creates a generator seeded from the system time
+    pub fn from_time() -> Rng {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Rng::from_seed(nanos)
+    }
+
+    /// Creates a generator that continues from a previously saved `state`
+    pub fn from_state(state: u64) -> Rng {
+        // xorshift must never have a zero state
+        Rng {
+            state: if state == 0 { 1 } else { state },
+        }
+    }
+
+    /// Reads the state written with `write_state` from the file at `path`
+    pub fn read_state(path: &Path) -> Result<Rng, WavGenError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| WavGenError::ReadError(path.to_path_buf(), e.into()))?;
+        let state = u64::from_str_radix(contents.trim(), 16)
+            .map_err(|_| WavGenError::RngStateParseError(path.to_path_buf()))?;
+
+        Ok(Rng::from_state(state))
+    }
+
+    /// Writes the current state to the file at `path`
+    pub fn write_state(&self, path: &Path) -> Result<(), WavGenError> {
+        fs::write(path, format!("{:016x}\n", self.state))
+            .map_err(|e| WavGenError::WriteError(path.to_path_buf(), e.into()))
+    }
+
+    /// Returns a uniformly distributed number in the range `[-1.0, 1.0)`
+    pub fn next_bipolar(&mut self) -> f32 {
+        // Use the top 24 bits as these are the best quality and fit exactly into the f32 mantissa
+        let unit = (self.next_u64() >> 40) as f32 / (1u32 << 24) as f32;
+        unit * 2. - 1.
+    }
+}
+
+impl RngCore for Rng {
+    fn next_u32(&mut self) -> u32 {
+        // The upper bits of xorshift64* are the best quality
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
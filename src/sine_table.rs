@@ -0,0 +1,71 @@
+//! A sine function from a lookup table with linear interpolation. This is faster than `f32::sin`
+//! for waves with many harmonics. With 4096 entries the error is below 1e-6, which is less than
+//! a hundredth of the step between 16 bit samples.
+//!
+//! The angle is given as a fixed point phase, the fraction of a cycle in units of 2^-64, so that
+//! the phase of each sample is found with an integer multiplication and wraps around exactly.
+use std::sync::OnceLock;
+
+/// The number of bits of the phase that index the table
+const TABLE_BITS: u32 = 12;
+
+/// The number of entries in a cycle of the table
+const TABLE_SIZE: usize = 1 << TABLE_BITS;
+
+/// A cycle of the sine, with the first entry repeated at the end so that the interpolation does
+/// not need to wrap
+pub struct SineTable {
+    entries: [f32; TABLE_SIZE + 1],
+}
+
+impl SineTable {
+    /// The table shared by all the generators, which is built when it is first used
+    pub fn get() -> &'static SineTable {
+        static TABLE: OnceLock<SineTable> = OnceLock::new();
+        TABLE.get_or_init(|| {
+            let mut entries = [0f32; TABLE_SIZE + 1];
+            for (i, entry) in entries.iter_mut().enumerate() {
+                *entry = (2. * std::f64::consts::PI * i as f64 / TABLE_SIZE as f64).sin() as f32;
+            }
+            SineTable { entries }
+        })
+    }
+
+    /// The sine of the fixed point `phase`, interpolated linearly between the entries
+    pub fn sin(&self, phase: u64) -> f32 {
+        let index = (phase >> (64 - TABLE_BITS)) as usize;
+        // The next 32 bits of the phase are the position between the entries
+        let weight = (phase >> (32 - TABLE_BITS)) as u32 as f32 / (1u64 << 32) as f32;
+
+        let entry = self.entries[index];
+        entry + (self.entries[index + 1] - entry) * weight
+    }
+}
+
+/// Converts an angle in `cycles` (i.e. radians / 2π) to a fixed point phase
+pub fn fixed_phase(cycles: f64) -> u64 {
+    let fraction = cycles - cycles.floor();
+    (fraction * (1u128 << 64) as f64) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_error_is_below_bound() {
+        let table = SineTable::get();
+
+        // Many phases that do not fall on the entries of the table
+        let steps = 1_000_003;
+        let max_error = (0..steps)
+            .map(|i| {
+                let cycles = i as f64 / steps as f64;
+                let exact = (2. * std::f32::consts::PI * cycles as f32).sin();
+                (table.sin(fixed_phase(cycles)) - exact).abs()
+            })
+            .fold(0f32, f32::max);
+
+        assert!(max_error < 1e-6, "largest error {}", max_error);
+    }
+}
@@ -0,0 +1,248 @@
+//! Writers for the variants of the wav format that the `wav` crate does not support
+use std::io::{self, Write};
+
+use wav::{BitDepth, Header};
+
+/// Writes the `track` as a big-endian RIFX file. This is the same as a wav (RIFF) file
+/// except that the identifier is `RIFX` and all the fields and samples are big-endian. As there
+/// is no RF64 form of RIFX, samples that are too large for the 32 bit sizes give an error.
+pub fn write_rifx<W: Write>(header: Header, track: &BitDepth, writer: &mut W) -> io::Result<()> {
+    let samples = sample_bytes_be(track)?;
+
+    // "WAVE" and the fmt chunk (8 + 16 bytes) and the data chunk header (8 bytes)
+    let riff_size = u32::try_from(4 + 24 + 8 + samples.len() as u64 + samples.len() as u64 % 2)
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the samples are too large for the 32 bit sizes of a RIFX file",
+            )
+        })?;
+
+    writer.write_all(b"RIFX")?;
+    writer.write_all(&riff_size.to_be_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_be_bytes())?;
+    writer.write_all(&header.audio_format.to_be_bytes())?;
+    writer.write_all(&header.channel_count.to_be_bytes())?;
+    writer.write_all(&header.sampling_rate.to_be_bytes())?;
+    writer.write_all(&header.bytes_per_second.to_be_bytes())?;
+    writer.write_all(&header.bytes_per_sample.to_be_bytes())?;
+    writer.write_all(&header.bits_per_sample.to_be_bytes())?;
+
+    writer.write_all(b"data")?;
+    // Fits as it is smaller than the RIFF size
+    writer.write_all(&(samples.len() as u32).to_be_bytes())?;
+    writer.write_all(&samples)?;
+
+    // Chunks are padded to an even length
+    if samples.len() % 2 == 1 {
+        writer.write_all(&[0])?;
+    }
+
+    writer.flush()
+}
+
+/// Size of the RIFF chunks in an RF64 file that are too small for the actual size
+const RF64_PLACEHOLDER_SIZE: u32 = 0xFFFF_FFFF;
+
+/// Writes the `track` as an RF64 (BW64, EBU Tech 3306) file. This is a wav file where the sizes of
+/// the RIFF and data chunks are held as 64 bit values in a `ds64` chunk, so that the data can be
+/// larger than 4GB. The 32 bit size fields are set to `0xFFFFFFFF`.
+pub fn write_rf64<W: Write>(header: Header, track: &BitDepth, writer: &mut W) -> io::Result<()> {
+    let data_size = track_len(track)? as u64 * (header.bits_per_sample / 8) as u64;
+    let sample_count = data_size / header.bytes_per_sample as u64;
+
+    // "WAVE", the ds64 chunk (8 + 28 bytes), the fmt chunk (8 + 16 bytes) and the data chunk header (8 bytes)
+    let riff_size = 4 + 36 + 24 + 8 + data_size + data_size % 2;
+
+    writer.write_all(b"RF64")?;
+    writer.write_all(&RF64_PLACEHOLDER_SIZE.to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"ds64")?;
+    writer.write_all(&28u32.to_le_bytes())?;
+    writer.write_all(&riff_size.to_le_bytes())?;
+    writer.write_all(&data_size.to_le_bytes())?;
+    writer.write_all(&sample_count.to_le_bytes())?;
+    // No table of the sizes of other chunks
+    writer.write_all(&0u32.to_le_bytes())?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&header.audio_format.to_le_bytes())?;
+    writer.write_all(&header.channel_count.to_le_bytes())?;
+    writer.write_all(&header.sampling_rate.to_le_bytes())?;
+    writer.write_all(&header.bytes_per_second.to_le_bytes())?;
+    writer.write_all(&header.bytes_per_sample.to_le_bytes())?;
+    writer.write_all(&header.bits_per_sample.to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&RF64_PLACEHOLDER_SIZE.to_le_bytes())?;
+    write_samples_le(track, writer)?;
+
+    if data_size % 2 == 1 {
+        writer.write_all(&[0])?;
+    }
+
+    writer.flush()
+}
+
+/// Writes the header of a wav file whose data chunk holds `data_size` bytes of samples. The
+/// samples are then written with `write_samples_le`, followed by a pad byte if `data_size` is odd.
+pub fn write_wav_header<W: Write>(
+    header: Header,
+    data_size: u32,
+    writer: &mut W,
+) -> io::Result<()> {
+    // "WAVE" and the fmt chunk (8 + 16 bytes) and the data chunk header (8 bytes)
+    let riff_size = 4 + 24 + 8 + data_size + data_size % 2;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&riff_size.to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&header.audio_format.to_le_bytes())?;
+    writer.write_all(&header.channel_count.to_le_bytes())?;
+    writer.write_all(&header.sampling_rate.to_le_bytes())?;
+    writer.write_all(&header.bytes_per_second.to_le_bytes())?;
+    writer.write_all(&header.bytes_per_sample.to_le_bytes())?;
+    writer.write_all(&header.bits_per_sample.to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())
+}
+
+/// Writes the samples of the `track` as little-endian bytes
+pub fn write_samples_le<W: Write>(track: &BitDepth, writer: &mut W) -> io::Result<()> {
+    // The samples are written one by one so that the (large) data is not copied
+    match track {
+        BitDepth::Eight(v) => writer.write_all(v)?,
+        BitDepth::Sixteen(v) => {
+            for s in v {
+                writer.write_all(&s.to_le_bytes())?;
+            }
+        }
+        // The 24 bit samples are held in the upper three bytes of the i32
+        BitDepth::TwentyFour(v) => {
+            for s in v {
+                writer.write_all(&s.to_le_bytes()[1..])?;
+            }
+        }
+        BitDepth::ThirtyTwoFloat(v) => {
+            for s in v {
+                writer.write_all(&s.to_le_bytes())?;
+            }
+        }
+        BitDepth::Empty => (),
+    }
+
+    Ok(())
+}
+
+/// The number of samples in the `track`
+fn track_len(track: &BitDepth) -> io::Result<usize> {
+    match track {
+        BitDepth::Eight(v) => Ok(v.len()),
+        BitDepth::Sixteen(v) => Ok(v.len()),
+        BitDepth::TwentyFour(v) => Ok(v.len()),
+        BitDepth::ThirtyTwoFloat(v) => Ok(v.len()),
+        BitDepth::Empty => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no samples to write",
+        )),
+    }
+}
+
+/// Converts the samples to big-endian bytes
+fn sample_bytes_be(track: &BitDepth) -> io::Result<Vec<u8>> {
+    match track {
+        BitDepth::Eight(v) => Ok(v.clone()),
+        BitDepth::Sixteen(v) => Ok(v.iter().flat_map(|s| s.to_be_bytes()).collect()),
+        // The 24 bit samples are held in the upper three bytes of the i32
+        BitDepth::TwentyFour(v) => Ok(v
+            .iter()
+            .flat_map(|s| s.to_be_bytes()[..3].to_vec())
+            .collect()),
+        BitDepth::ThirtyTwoFloat(v) => Ok(v.iter().flat_map(|s| s.to_be_bytes()).collect()),
+        BitDepth::Empty => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no samples to write",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn test_header() -> Header {
+        Header::new(wav::header::WAV_FORMAT_PCM, 2, 44100, 16)
+    }
+
+    #[test]
+    fn rifx_round_trip() {
+        let samples: Vec<i16> = vec![0, 1, -1, 0x1234, i16::MIN, i16::MAX];
+        let mut rifx = Vec::new();
+        write_rifx(
+            test_header(),
+            &BitDepth::Sixteen(samples.clone()),
+            &mut rifx,
+        )
+        .unwrap();
+        assert_eq!(&rifx[..4], b"RIFX");
+
+        // Swapping the byte order of the fields and the samples gives a wav file
+        let mut riff = rifx.clone();
+        riff[..4].copy_from_slice(b"RIFF");
+        for field in [
+            4..8,
+            16..20,
+            20..22,
+            22..24,
+            24..28,
+            28..32,
+            32..34,
+            34..36,
+            40..44,
+        ] {
+            riff[field].reverse();
+        }
+        for sample in riff[44..].chunks_mut(2) {
+            sample.reverse();
+        }
+
+        let (header, track) = wav::read(&mut Cursor::new(riff)).unwrap();
+        assert_eq!(header, test_header());
+        assert_eq!(track, BitDepth::Sixteen(samples));
+    }
+
+    #[test]
+    fn rf64_ds64_sizes() {
+        // 3 stereo frames of 24 bit samples, i.e. 18 bytes of data
+        let track = BitDepth::TwentyFour(vec![0, 1 << 8, -1 << 8, 2 << 8, -2 << 8, 3 << 8]);
+        let header = Header::new(wav::header::WAV_FORMAT_PCM, 2, 48000, 24);
+        let mut rf64 = Vec::new();
+        write_rf64(header, &track, &mut rf64).unwrap();
+
+        let u32_at = |i: usize| u32::from_le_bytes(rf64[i..i + 4].try_into().unwrap());
+        let u64_at = |i: usize| u64::from_le_bytes(rf64[i..i + 8].try_into().unwrap());
+        assert_eq!(&rf64[..4], b"RF64");
+        assert_eq!(u32_at(4), RF64_PLACEHOLDER_SIZE);
+        assert_eq!(&rf64[12..16], b"ds64");
+        assert_eq!(u32_at(16), 28);
+
+        // The RIFF size excludes the "RF64" identifier and the size itself
+        assert_eq!(u64_at(20), rf64.len() as u64 - 8);
+        assert_eq!(u64_at(28), 18);
+        assert_eq!(u64_at(36), 3);
+
+        assert_eq!(&rf64[72..76], b"data");
+        assert_eq!(u32_at(76), RF64_PLACEHOLDER_SIZE);
+        assert_eq!(rf64.len(), 80 + 18);
+    }
+}